@@ -0,0 +1,181 @@
+use std::cmp::Reverse;
+use std::time::{Duration, Instant};
+
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+
+use super::database::{sibling, CJDatabase, Chinese};
+use super::game::{self, Mode, Quiz, Round, Settings, Ui};
+use super::highscore;
+use super::history;
+use super::radical::{self, Components};
+
+// Survival against the clock: answer as many questions as possible before
+// the time runs out.  A correct answer gains time, a wrong one loses time.
+// The characters known best come first, then the new ones, then those
+// answered wrong, so the game gets harder the longer it lasts.
+// The scores go on a board of their own, see highscore::ARCADE_FILE.
+
+const START: Duration = Duration::from_secs(60); // time on the clock at first
+const BONUS: Duration = Duration::from_secs(3); // gained by a correct answer
+const PENALTY: Duration = Duration::from_secs(5); // lost by a wrong answer
+
+// Countdown of an arcade game.
+pub struct Clock {
+    pub deadline: Instant,
+}
+
+impl Clock {
+    pub fn new(time: Duration) -> Clock {
+        Clock {
+            deadline: Instant::now() + time,
+        }
+    }
+
+    pub fn gain(&mut self, time: Duration) {
+        self.deadline += time;
+    }
+
+    pub fn lose(&mut self, time: Duration) {
+        self.deadline = self.deadline.checked_sub(time).unwrap_or_else(Instant::now);
+    }
+
+    // Time left, zero once the time is up.
+    pub fn left(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.left().is_zero()
+    }
+}
+
+// The characters of the database, the highest rated first, then the
+// shortest codes, in random order otherwise.
+pub fn ramp(db: &CJDatabase) -> Vec<Chinese> {
+    let mut items: Vec<Chinese> =
+        db.v.iter()
+            .filter(|x| !x.code.is_empty())
+            .cloned()
+            .collect();
+    items.shuffle(&mut thread_rng());
+    items.sort_by_key(|x| (Reverse(x.rating), x.code.len()));
+    items
+}
+
+// Play arcade games until the user quits.
+// Each answer is rated like the answers of a round.
+pub fn play(db: &mut CJDatabase, settings: &Settings, ui: &mut dyn Ui) {
+    let components = Components::load(&radical::components_path(&db.path));
+    let mut mode = settings.mode;
+    ui.banner();
+    loop {
+        mode = match ui.ask_continue(mode) {
+            Some(mode) => mode,
+            None => return,
+        };
+        game::use_ratings(db, mode, settings.user.as_deref());
+        let (round, late) = survive(ui, db, mode, settings, &components);
+        ui.report(&format!(
+            "Game over!  {} correct in {} seconds",
+            round.correct,
+            round.time.as_secs()
+        ));
+        if late {
+            ui.report("The last answer came after the time ran out, it does not count.");
+        }
+        game::high_score(
+            ui,
+            &sibling(&db.path, highscore::ARCADE_FILE),
+            mode,
+            0,
+            &round,
+        );
+        if let Err(e) = history::append(&history::history_path(&db.path), &round.answers) {
+            eprintln!("Unable to save the history: {}", e);
+        }
+        db.update(round.items);
+        if let Err(msg) = db.save() {
+            eprintln!("{}", msg);
+        }
+    }
+}
+
+// Ask questions until the time runs out.  The answer being typed then
+// is the last one, it is neither counted nor rated.
+// Return the round, and true if an answer came too late.
+fn survive(
+    ui: &mut dyn Ui,
+    db: &CJDatabase,
+    mode: Mode,
+    settings: &Settings,
+    components: &Components,
+) -> (Round, bool) {
+    let quiz = Quiz {
+        db,
+        mode,
+        settings,
+        components,
+    };
+    let mut round = Round::default();
+    let mut clock = Clock::new(START);
+    ui.begin(0);
+    let start = Instant::now();
+    let mut late = false;
+    for (i, chin) in ramp(db).into_iter().enumerate() {
+        ui.countdown(Some(clock.deadline));
+        let answered = round.items.len();
+        let scored = game::challenge(ui, &quiz, chin, (i + 1, 0), "", &mut round);
+        if clock.is_over() {
+            // the review and the score of the answer are taken back
+            late = round.items.len() > answered;
+            round.items.truncate(answered);
+            if scored {
+                round.correct -= 1;
+            }
+            break;
+        }
+        // a correct answer with the code shown neither gains nor loses
+        match round.answers.last() {
+            _ if scored => clock.gain(BONUS),
+            Some(x) if !x.correct => clock.lose(PENALTY),
+            _ => {}
+        }
+        if clock.is_over() {
+            break;
+        }
+    }
+    round.time = start.elapsed();
+    ui.countdown(None);
+    ui.score(round.correct, round.items.len(), round.time);
+    (round, late)
+}
+
+#[test]
+fn test_clock() {
+    let mut clock = Clock::new(START);
+    assert!(!clock.is_over());
+    assert!(clock.left() <= START && clock.left() > START - BONUS);
+    clock.gain(BONUS);
+    assert!(clock.left() > START);
+    clock.lose(START + BONUS + PENALTY);
+    assert!(clock.is_over());
+    assert_eq!(clock.left(), Duration::ZERO);
+}
+
+#[test]
+fn test_ramp() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj06.csv");
+    let items = ramp(&db);
+    assert_eq!(items.len(), db.v.len());
+    assert!(items.windows(2).all(|x| x[0].rating >= x[1].rating));
+    assert_eq!(
+        items[0].rating,
+        db.v.iter().map(|x| x.rating).max().unwrap()
+    );
+    assert!(items
+        .windows(2)
+        .filter(|x| x[0].rating == x[1].rating)
+        .all(|x| x[0].code.len() <= x[1].code.len()));
+}
@@ -0,0 +1,112 @@
+use std::cmp::Reverse;
+
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+
+use super::database::CJDatabase;
+use super::profile;
+
+// Multiple choice, for beginners: the character is shown with a few
+// codes to pick from, answered with keys 1 to 4.  Recognising a code is
+// easier than typing it, so the recognition ratings are kept apart from
+// the code ratings, in a progress file of their own, see progress_path().
+
+pub const CHOICES: usize = 4; // codes to pick from, with the right one
+
+// Return the codes to pick from in random order: a code of the character
+// and codes of other characters which look like it, see distractors().
+pub fn options(db: &CJDatabase, code: &str, codes: &[String]) -> Vec<String> {
+    let mut options = distractors(db, code, codes, CHOICES - 1);
+    options.push(code.to_string());
+    options.shuffle(&mut thread_rng());
+    options
+}
+
+// Return codes of other characters to mix with a code: those sharing the
+// longest beginning with it, which start with the same radical, then
+// those of a similar length.  Any other codes make up the number if
+// there are not enough.  codes: all codes of the character, left out.
+pub fn distractors(db: &CJDatabase, code: &str, codes: &[String], count: usize) -> Vec<String> {
+    let mut others: Vec<&str> =
+        db.v.iter()
+            .map(|x| x.code.as_str())
+            .filter(|x| !x.is_empty() && !codes.iter().any(|y| y == x))
+            .collect();
+    others.sort();
+    others.dedup();
+    others.shuffle(&mut thread_rng());
+    let shared = |x: &str| {
+        x.chars()
+            .zip(code.chars())
+            .take_while(|(a, b)| a == b)
+            .count()
+    };
+    let (mut similar, mut rest): (Vec<&str>, Vec<&str>) =
+        others.into_iter().partition(|x| shared(x) > 0);
+    similar.sort_by_key(|x| (Reverse(shared(x)), x.len().abs_diff(code.len())));
+    similar.append(&mut rest);
+    similar.into_iter().take(count).map(String::from).collect()
+}
+
+// Return the code picked by its number from 1, None if what was typed
+// is not the number of an option.
+pub fn picked(options: &[String], typed: &str) -> Option<String> {
+    match typed.parse::<usize>() {
+        Ok(n) if (1..=options.len()).contains(&n) => Some(options[n - 1].clone()),
+        _ => None,
+    }
+}
+
+// Return the number from 1 of the option picked with a key, if any.
+pub fn number(key: char, count: usize) -> Option<usize> {
+    key.to_digit(10)
+        .map(|n| n as usize)
+        .filter(|n| (1..=count).contains(n))
+}
+
+// Return the progress file of the recognition ratings:
+// <data folder>/choice.csv, or <data folder>/profiles/<name>.choice.csv
+// for a user profile.
+pub fn progress_path(data: &str, user: Option<&str>) -> String {
+    profile::mode_progress_path(data, user, "choice")
+}
+
+#[test]
+fn test_distractors() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj05.csv");
+
+    let codes = db.codes_of("題");
+    let found = distractors(&db, "aombc", &codes, CHOICES - 1);
+    assert_eq!(found.len(), CHOICES - 1);
+    assert!(found
+        .iter()
+        .all(|x| x.starts_with("ao") && !codes.contains(x)));
+    assert!(found
+        .iter()
+        .all(|x| found.iter().filter(|y| *y == x).count() == 1));
+
+    let options = options(&db, "aombc", &codes);
+    assert_eq!(options.len(), CHOICES);
+    assert_eq!(options.iter().filter(|x| *x == "aombc").count(), 1);
+
+    // not enough codes of the same radical
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj06.csv");
+    let found = distractors(&db, "zz", &[], 5);
+    assert_eq!(found.len(), 5);
+}
+
+#[test]
+fn test_picked() {
+    let options: Vec<String> = ["ab", "abc", "a", "b"].map(String::from).to_vec();
+    assert_eq!(picked(&options, "2").as_deref(), Some("abc"));
+    assert_eq!(picked(&options, "4").as_deref(), Some("b"));
+    assert_eq!(picked(&options, "5"), None);
+    assert_eq!(picked(&options, "0"), None);
+    assert_eq!(picked(&options, "ab"), None);
+    assert_eq!(picked(&options, "abc"), None);
+    assert_eq!(number('3', CHOICES), Some(3));
+    assert_eq!(number('5', CHOICES), None);
+    assert_eq!(number('a', CHOICES), None);
+}
@@ -0,0 +1,168 @@
+use clap::{Args, Parser, Subcommand};
+
+use super::game::Mode;
+use super::table::Format;
+use super::tui::Frontend;
+
+#[derive(Parser)]
+#[command(name = "cj", version, about = "Cangjie input method challenges")]
+pub struct Cli {
+    /// Data file [default: ./data/cj.csv]
+    #[arg(long, global = true)]
+    pub data: Option<String>,
+
+    /// Config file [default: ~/.config/cj/config.toml]
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+
+    /// Colour output on/off
+    #[arg(long, global = true)]
+    pub color: Option<bool>,
+
+    /// User profile, with its own ratings
+    #[arg(long, global = true)]
+    pub user: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Play rounds of challenges (default)
+    Play(PlayArgs),
+    /// Show statistics of the database
+    Stats {
+        /// The ratings of the 速成 quick codes
+        #[arg(long)]
+        quick: bool,
+        /// The recognition ratings of the multiple choice mode
+        #[arg(long, conflicts_with = "quick")]
+        choice: bool,
+    },
+    /// Reset the ratings of all characters, or of one code
+    Reset {
+        /// Only reset the characters of this code
+        #[arg(long)]
+        code: Option<String>,
+        /// Do not ask for confirmation
+        #[arg(long, short)]
+        yes: bool,
+    },
+    /// Add a character, e.g. cj add hqi 我, or hqi/5 for Cangjie 5 only
+    Add { code: String, char: String },
+    /// Look up the codes of characters, or the characters of a code
+    Lookup { query: String },
+    /// Import records from a data file or an input method table, keeping
+    /// existing ratings
+    Import {
+        file: String,
+        /// Format of the file, csv for a data file
+        #[arg(long, value_enum, default_value_t = Format::Auto)]
+        format: Format,
+        /// Mark the imported codes as valid in this Cangjie version only
+        #[arg(long)]
+        cangjie: Option<u8>,
+    },
+    /// Export the database to a data file
+    Export { file: String },
+    /// Show the high score boards
+    Scores {
+        /// Only the board of this quiz mode
+        #[arg(long, value_enum)]
+        mode: Option<Mode>,
+        /// Only the board of this round size
+        #[arg(long, short = 'n')]
+        count: Option<usize>,
+        /// Number of entries of a board, at most 10
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+        /// The boards of the arcade games
+        #[arg(long)]
+        arcade: bool,
+    },
+    /// List the user profiles
+    Profiles,
+    /// Type a passage character by character, from a text file or built in
+    Passage(PassageArgs),
+    /// Answer as many questions as possible before the time runs out
+    Arcade(ArcadeArgs),
+    /// List the lessons and their completion, or show one lesson
+    Lessons { number: Option<usize> },
+    /// Import a character frequency list, most frequent first
+    Frequency { file: String },
+    /// List the characters mixed up the most in the wrong answers
+    Confusions {
+        /// Number of pairs listed
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+    },
+    /// Check the data file and the phrase file for problems
+    Doctor {
+        /// Fix the problems, removing lines beyond repair
+        #[arg(long)]
+        fix: bool,
+    },
+}
+
+#[derive(Args, Default)]
+pub struct PlayArgs {
+    /// Number of questions in a round [default: 10]
+    #[arg(long, short = 'n')]
+    pub count: Option<usize>,
+    /// Quiz mode [default: char]
+    #[arg(long, value_enum)]
+    pub mode: Option<Mode>,
+    /// Character selection strategy [default: smart]
+    #[arg(long)]
+    pub strategy: Option<String>,
+    /// Play a lesson, see cj lessons, in code mode unless --mode is given
+    #[arg(long, conflicts_with = "strategy")]
+    pub lesson: Option<usize>,
+    /// Only the N most frequent characters, curriculum strategy [default: all]
+    #[arg(long)]
+    pub top: Option<usize>,
+    /// Practice the character after a wrong answer [default: true]
+    #[arg(long)]
+    pub practice: Option<bool>,
+    /// Cangjie version practised, 3 or 5 [default: any]
+    #[arg(long)]
+    pub cangjie: Option<u8>,
+    /// Front end, full screen or line by line [default: auto]
+    #[arg(long, value_enum)]
+    pub ui: Option<Frontend>,
+}
+
+#[derive(Args, Default)]
+pub struct PassageArgs {
+    /// UTF-8 text file [default: a built-in passage]
+    pub file: Option<String>,
+    /// Built-in passage, see --list
+    #[arg(long, short = 'n')]
+    pub number: Option<usize>,
+    /// List the built-in passages
+    #[arg(long)]
+    pub list: bool,
+    /// Quiz mode [default: code]
+    #[arg(long, value_enum)]
+    pub mode: Option<Mode>,
+    /// Cangjie version practised, 3 or 5 [default: any]
+    #[arg(long)]
+    pub cangjie: Option<u8>,
+    /// Front end, full screen or line by line [default: auto]
+    #[arg(long, value_enum)]
+    pub ui: Option<Frontend>,
+}
+
+#[derive(Args, Default)]
+pub struct ArcadeArgs {
+    /// Quiz mode [default: code]
+    #[arg(long, value_enum)]
+    pub mode: Option<Mode>,
+    /// Cangjie version practised, 3 or 5 [default: any]
+    #[arg(long)]
+    pub cangjie: Option<u8>,
+    /// Front end, full screen or line by line [default: auto]
+    #[arg(long, value_enum)]
+    pub ui: Option<Frontend>,
+}
@@ -0,0 +1,388 @@
+use std::io;
+use std::path::Path;
+
+use super::confusion;
+use super::database::{
+    is_valid_code, parse_version, sibling, CJDatabase, Chinese, Problem, Version, VERSIONS,
+};
+use super::frequency::{self, Frequency};
+use super::game::Mode;
+use super::highscore;
+use super::history;
+use super::lesson;
+use super::phrase;
+use super::profile;
+use super::radical;
+use super::schedule;
+use super::table::{self, Format};
+
+// Show statistics of the database, and of the answers in the history.
+pub fn stats(db: &CJDatabase, user: Option<&str>) {
+    let now = schedule::now();
+    let count = |f: &dyn Fn(&Chinese) -> bool| db.v.iter().filter(|x| f(x)).count();
+
+    println!("Records:        {:6}", db.v.len());
+    println!(
+        "New:            {:6}",
+        count(&|x| x.rating == 0 && x.due == 0)
+    );
+    println!("Tested:         {:6}", count(&|x| x.due > 0));
+    println!("  wrong:        {:6}", count(&|x| x.rating < 0));
+    println!(
+        "  rating 1-3:   {:6}",
+        count(&|x| x.rating > 0 && x.rating <= 3)
+    );
+    println!("  rating > 3:   {:6}", count(&|x| x.rating > 3));
+    println!("  shaky:        {:6}", count(&|x| schedule::is_shaky(x)));
+    println!("Due for review: {:6}", count(&|x| schedule::is_due(x, now)));
+
+    let answers = history::load(&history::history_path(&db.path), Some(user.unwrap_or("")));
+    let week: Vec<&history::Answer> = answers
+        .iter()
+        .filter(|x| x.time + 7 * 24 * 60 * 60 > now)
+        .collect();
+    let correct = |v: &[&history::Answer]| v.iter().filter(|x| x.correct).count();
+    let all: Vec<&history::Answer> = answers.iter().collect();
+
+    println!("Answers:        {:6}", all.len());
+    if !all.is_empty() {
+        println!("  correct:      {:5}%", correct(&all) * 100 / all.len());
+    }
+    println!("  last 7 days:  {:6}", week.len());
+    if !week.is_empty() {
+        println!("  correct:      {:5}%", correct(&week) * 100 / week.len());
+    }
+}
+
+// Reset the ratings of the characters of a code, or of all characters.
+// Ask for confirmation before resetting all characters.
+pub fn reset(db: &mut CJDatabase, code: Option<String>, yes: bool) -> Result<(), String> {
+    let code = code.map(|x| x.to_lowercase());
+    if code.is_none() && !yes {
+        println!("Reset the ratings of all characters? [y/N]");
+        let mut line = String::new();
+        io::stdin()
+            .read_line(&mut line)
+            .map_err(|e| e.to_string())?;
+        if !line.trim().eq_ignore_ascii_case("y") {
+            return Ok(());
+        }
+    }
+
+    let count = db.reset(code.as_deref());
+    if count == 0 {
+        return Err(format!("no such code: {}", code.unwrap_or_default()));
+    }
+    db.save()?;
+    println!("Records reset: {}", count);
+    Ok(())
+}
+
+// Add a character to the database.
+pub fn add(db: &mut CJDatabase, code: &str, char: &str) -> Result<(), String> {
+    // e.g. hqi/5 for a code of Cangjie 5 only
+    let (code, version) = match code.split_once('/') {
+        Some((code, v)) => match parse_version(v) {
+            Some(version) => (code.to_lowercase(), version),
+            None => return Err(format!("unknown Cangjie version: {} (3 or 5)", v)),
+        },
+        None => (code.to_lowercase(), Version::Any),
+    };
+    if !is_valid_code(&code) {
+        return Err(format!("invalid cangjie code: {}", code));
+    }
+    if char.chars().count() != 1 {
+        return Err(format!("not a single character: {}", char));
+    }
+
+    if db.find(&code, char).is_some() {
+        return Err(format!("already exists: {},{}", code, char));
+    }
+    db.merge(vec![Chinese {
+        char: char.to_string(),
+        code: code.clone(),
+        version,
+        ..Default::default()
+    }]);
+    db.save()?;
+    println!("Added: {}{},{}", code, version.suffix(), char);
+    Ok(())
+}
+
+// Look up the codes of characters, or the characters of a code.
+pub fn lookup(db: &CJDatabase, query: &str) -> Result<(), String> {
+    let found: Vec<&Chinese> = if query.chars().all(|x| x.is_ascii_alphabetic()) {
+        db.find_code(&query.to_lowercase())
+    } else {
+        query
+            .chars()
+            .flat_map(|c| db.find_char(&c.to_string()))
+            .collect()
+    };
+
+    if found.is_empty() {
+        return Err(format!("not found: {}", query));
+    }
+    let ranks = Frequency::load(&frequency::frequency_path(&db.path));
+    for x in found {
+        let median = match x.median() {
+            Some(t) => format!(" median {:.1}s", t as f64 / 1000.0),
+            None => String::new(),
+        };
+        let rank = match ranks.rank(&x.char) {
+            Some(r) => format!(" frequency #{}", r),
+            None => String::new(),
+        };
+        println!(
+            "{} {:5} {} rating {}{}{}",
+            x.char,
+            format!("{}{}", x.code, x.version.suffix()),
+            radical::radicals(&x.code),
+            x.rating,
+            median,
+            rank
+        );
+    }
+    Ok(())
+}
+
+// Import the records of a data file or of an input method table, keeping
+// the ratings of the records already in the database.
+pub fn import(
+    db: &mut CJDatabase,
+    file: &str,
+    format: Format,
+    cangjie: Option<u8>,
+) -> Result<(), String> {
+    if !std::path::Path::new(file).exists() {
+        return Err(format!("no such file: {}", file));
+    }
+    let version = match cangjie {
+        Some(v) if VERSIONS.contains(&v) => Version::Only(v),
+        Some(v) => return Err(format!("unknown Cangjie version: {} (3 or 5)", v)),
+        None => Version::Any,
+    };
+    let format = match format {
+        Format::Auto => {
+            let text = std::fs::read_to_string(file).unwrap_or_default();
+            table::detect(file, &text)
+        }
+        format => format,
+    };
+
+    let records = if format == Format::Csv {
+        let mut other = CJDatabase::new(file);
+        other.load();
+        if cangjie.is_some() {
+            for x in other.v.iter_mut() {
+                x.version = version;
+            }
+        }
+        other.v
+    } else {
+        let table = table::read(file, format, version)?;
+        println!(
+            "Entries read: {}, skipped: {}",
+            table.records.len(),
+            table.skipped
+        );
+        table.records
+    };
+    let added = db.merge(records);
+    db.save()?;
+    println!("Records added: {}", added);
+    Ok(())
+}
+
+// List the lessons with the characters learnt of each, or show the keys
+// and characters of a lesson.
+pub fn lessons(db: &CJDatabase, number: Option<usize>) -> Result<(), String> {
+    let ranks = Frequency::load(&frequency::frequency_path(&db.path));
+    let Some(number) = number else {
+        for x in lesson::lessons() {
+            let (passed, total) = lesson::progress(&x.items(db, &ranks));
+            let done = if total > 0 && passed == total {
+                "done"
+            } else {
+                ""
+            };
+            println!(
+                "{:2}. {:2}/{:2} {:4} {}",
+                x.number,
+                passed,
+                total,
+                done,
+                x.title()
+            );
+        }
+        println!("Play a lesson with cj play --lesson N");
+        return Ok(());
+    };
+
+    let x = lesson::lesson(number).ok_or(format!("no such lesson: {}", number))?;
+    println!("Lesson {}: {}", x.number, x.title());
+    for key in x.keys() {
+        println!("  {}", key);
+    }
+    let items = x.items(db, &ranks);
+    for y in items.iter() {
+        let mark = if y.rating > 0 { "✓" } else { " " };
+        println!(
+            "  {} {} {:5} {}",
+            mark,
+            y.char,
+            y.code,
+            radical::radicals(&y.code)
+        );
+    }
+    let (passed, total) = lesson::progress(&items);
+    println!("Learnt: {}/{}", passed, total);
+    Ok(())
+}
+
+// Import a character frequency list into the frequency file next to the
+// data file, replacing the ranks there.
+pub fn frequency(db: &CJDatabase, file: &str) -> Result<(), String> {
+    let text =
+        std::fs::read_to_string(file).map_err(|e| format!("unable to read {}: {}", file, e))?;
+    let (chars, skipped) = frequency::parse_list(&text);
+    if chars.is_empty() {
+        return Err(format!("no characters found in {}", file));
+    }
+    let path = frequency::frequency_path(&db.path);
+    frequency::save(&path, &chars)?;
+    let known = chars.iter().filter(|x| !db.find_char(x).is_empty()).count();
+    println!(
+        "Characters ranked: {}, skipped lines: {}",
+        chars.len(),
+        skipped
+    );
+    println!("  in the database: {}", known);
+    println!("Saved to {}", path);
+    Ok(())
+}
+
+// Show the high score boards, of the rounds or of the arcade games,
+// of a mode and round size if given.  The boards keep highscore::TOP
+// entries, no more can be shown.
+pub fn scores(
+    db: &CJDatabase,
+    mode: Option<Mode>,
+    count: Option<usize>,
+    top: usize,
+    arcade: bool,
+) -> Result<(), String> {
+    if top > highscore::TOP {
+        return Err(format!(
+            "--top {} is more than the {} entries a board keeps",
+            top,
+            highscore::TOP
+        ));
+    }
+    let board = match arcade {
+        true => highscore::ARCADE_FILE,
+        false => highscore::SCORES_FILE,
+    };
+    let entries = highscore::load(&sibling(&db.path, board));
+    let mut boards: Vec<(String, usize)> = entries
+        .iter()
+        .filter(|x| mode.is_none_or(|m| x.mode == m.key()))
+        .filter(|x| count.is_none_or(|n| x.count == n))
+        .map(|x| (x.mode.clone(), x.count))
+        .collect();
+    boards.sort();
+    boards.dedup();
+
+    if boards.is_empty() {
+        println!("No high scores yet.");
+    }
+    for (mode, count) in boards {
+        let title = highscore::title(&mode, count);
+        highscore::show(&highscore::top(&entries, &mode, count, top), &title);
+    }
+    Ok(())
+}
+
+// List the pairs of characters mixed up the most in the wrong answers,
+// with their codes and how they are alike.
+pub fn confusions(db: &CJDatabase, user: Option<&str>, top: usize) {
+    let answers = history::load(&history::history_path(&db.path), Some(user.unwrap_or("")));
+    let found = confusion::tally(db, &answers);
+    if found.is_empty() {
+        println!("No characters mixed up yet.");
+        return;
+    }
+    for x in found.iter().take(top) {
+        let (a, b) = &x.pair;
+        let code = |c: &str| db.codes_of(c).join("/");
+        println!(
+            "{:>4}x  {} {:6} {} {:6} {:13} last {}",
+            x.count,
+            a,
+            code(a),
+            b,
+            code(b),
+            confusion::likeness(db, a, b),
+            highscore::date(x.last)
+        );
+    }
+    println!("Drill them with cj play --strategy confusions");
+}
+
+// List the user profiles.
+pub fn profiles(db: &CJDatabase) {
+    let names = profile::list(&db.path);
+    if names.is_empty() {
+        println!("No profiles yet, play with --user NAME to create one.");
+    }
+    for name in names {
+        println!("{}", name);
+    }
+}
+
+// Report the problems of the data file and of the phrase file, if there
+// is one, and fix them if asked to.
+pub fn doctor(db: &mut CJDatabase, fix: bool) -> Result<(), String> {
+    examine(db, fix)?;
+    let path = phrase::phrases_path(&db.path);
+    if Path::new(&path).exists() {
+        examine(&mut phrase::open(&path, db), fix)?;
+    }
+    Ok(())
+}
+
+// Report the problems of a file of records, and fix them if asked to.
+// Fixing saves the records as loaded: fixable lines are corrected,
+// comment lines are kept and the other problem lines are removed.
+fn examine(db: &mut CJDatabase, fix: bool) -> Result<(), String> {
+    if db.issues.is_empty() {
+        println!("No problems found in {}", db.path);
+        return Ok(());
+    }
+
+    for x in &db.issues {
+        let action = match (x.problem, x.kept) {
+            (Problem::Comment, _) => "kept",
+            (_, true) => "fixable",
+            (_, false) => "removed on fix",
+        };
+        println!("Line {:5}: {} ({}): {}", x.line, x.problem, action, x.text);
+    }
+    println!("Problems found in {}: {}", db.path, db.issues.len());
+
+    if fix {
+        db.issues.clear();
+        db.save()?;
+        println!("Fixed: {}", db.path);
+    } else {
+        println!("Run cj doctor --fix to fix them, or edit the file.");
+    }
+    Ok(())
+}
+
+// Export the database to a data file.
+pub fn export(db: &mut CJDatabase, file: &str) -> Result<(), String> {
+    db.save_as(file);
+    println!("Records exported: {}", db.v.len());
+    Ok(())
+}
@@ -0,0 +1,113 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+
+use super::database::SmartWeights;
+use super::game::Mode;
+use super::tui::Frontend;
+
+// Settings read from the config file.
+// Everything is optional, command line options take precedence.
+//
+// Example:
+//   data = "/home/me/cj/cj.csv"
+//   count = 20
+//   mode = "code"
+//   strategy = "smart"
+//   top = 1000
+//   color = true
+//   practice = true
+//   user = "amy"
+//   ui = "auto"
+//   cangjie = 5
+//
+//   [weights]
+//   due = 0.5
+//   difficult = 0.1
+//   shaky = 0.2
+//   new = 0.8
+#[derive(Debug, Default, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub data: Option<String>,     // data file
+    pub count: Option<usize>,     // number of questions in a round
+    pub mode: Option<Mode>,       // default quiz mode
+    pub strategy: Option<String>, // character selection strategy
+    pub top: Option<usize>,       // only the N most frequent characters, curriculum strategy
+    pub color: Option<bool>,      // colour output on/off
+    pub practice: Option<bool>,   // practice the character after a wrong answer
+    pub user: Option<String>,     // user profile
+    pub ui: Option<Frontend>,     // full screen or line by line front end
+    pub cangjie: Option<u8>,      // cangjie version practised, 3 or 5
+    pub weights: SmartWeights,    // quotas of the smart strategy
+}
+
+impl Config {
+    // Default location of the config file, e.g. ~/.config/cj/config.toml
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::config_dir().map(|x| x.join("cj").join("config.toml"))
+    }
+
+    // Load the config file.
+    // A missing config file gives the default settings.
+    pub fn load(path: Option<&str>) -> Result<Config, String> {
+        let path = match path {
+            Some(x) => PathBuf::from(x),
+            None => match Config::default_path() {
+                Some(x) if x.exists() => x,
+                _ => return Ok(Config::default()),
+            },
+        };
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("unable to read {}: {}", path.display(), e))?;
+        Config::parse(&text).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn parse(text: &str) -> Result<Config, String> {
+        toml::from_str(text).map_err(|e| e.to_string())
+    }
+}
+
+#[test]
+fn test_config_parse() {
+    let config = Config::parse(
+        r#"
+data = "/tmp/cj.csv"
+count = 20
+mode = "code"
+strategy = "weakest"
+color = false
+practice = false
+
+[weights]
+new = 0.5
+"#,
+    )
+    .unwrap();
+    assert_eq!(config.data.as_deref(), Some("/tmp/cj.csv"));
+    assert_eq!(config.count, Some(20));
+    assert_eq!(config.mode, Some(Mode::Code));
+    assert_eq!(config.strategy.as_deref(), Some("weakest"));
+    assert_eq!(config.color, Some(false));
+    assert_eq!(config.practice, Some(false));
+    assert_eq!(config.weights.new, 0.5);
+    assert_eq!(config.weights.due, SmartWeights::default().due);
+}
+
+#[test]
+fn test_config_parse_empty() {
+    assert_eq!(Config::parse("").unwrap(), Config::default());
+}
+
+#[test]
+fn test_config_parse_error() {
+    assert!(Config::parse("count = \"ten\"").is_err());
+    assert!(Config::parse("colour = true").is_err());
+    assert!(Config::parse("mode = \"morse\"").is_err());
+}
+
+#[test]
+fn test_config_load_missing() {
+    assert!(Config::load(Some("./tests/no_such_config.toml")).is_err());
+}
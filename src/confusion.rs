@@ -0,0 +1,227 @@
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+
+use super::database::{CJDatabase, Chinese};
+use super::history::{self, Answer};
+use super::strategy::{SelectionStrategy, Smart};
+
+// Characters which are mixed up, e.g. 弘 and 夕 or 忍 and 慰, found in the
+// wrong answers of the history: what was typed for what was expected.
+// In the code modes a wrong code is the code of another character, in the
+// character modes another character was typed.  Phrases and quick codes,
+// which many characters share, are left out.
+
+// A pair of characters mixed up, in either direction.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Confusion {
+    pub pair: (String, String), // in the order first mixed up
+    pub count: usize,           // wrong answers mixing them up
+    pub last: u64,              // time of the last one
+}
+
+// Return the characters a wrong answer mixed up with the one expected.
+pub fn confused_with(db: &CJDatabase, answer: &Answer) -> Vec<String> {
+    if answer.correct || answer.char.chars().count() != 1 {
+        return Vec::new();
+    }
+    let chars = match answer.mode.as_str() {
+        "char" | "reverse" if answer.typed.chars().count() == 1 => vec![answer.typed.clone()],
+        "code" | "phrase" | "choice" => db.chars_of(&answer.typed),
+        _ => Vec::new(),
+    };
+    chars.into_iter().filter(|x| *x != answer.char).collect()
+}
+
+// Count the pairs of characters mixed up in the answers, the most often
+// mixed up first, then the most recent.
+pub fn tally(db: &CJDatabase, answers: &[Answer]) -> Vec<Confusion> {
+    let mut found: Vec<Confusion> = Vec::new();
+    for answer in answers {
+        for other in confused_with(db, answer) {
+            let same = |x: &&mut Confusion| {
+                x.pair == (answer.char.clone(), other.clone())
+                    || x.pair == (other.clone(), answer.char.clone())
+            };
+            match found.iter_mut().find(same) {
+                Some(x) => {
+                    x.count += 1;
+                    x.last = x.last.max(answer.time);
+                }
+                None => found.push(Confusion {
+                    pair: (answer.char.clone(), other),
+                    count: 1,
+                    last: answer.time,
+                }),
+            }
+        }
+    }
+    found.sort_by(|a, b| b.count.cmp(&a.count).then(b.last.cmp(&a.last)));
+    found
+}
+
+// Return true if two codes are one keystroke apart: a letter changed,
+// added or left out, e.g. "ni" and "nii".
+pub fn one_key_apart(a: &str, b: &str) -> bool {
+    let (short, long) = if a.len() <= b.len() { (a, b) } else { (b, a) };
+    match long.len() - short.len() {
+        0 => {
+            short
+                .chars()
+                .zip(long.chars())
+                .filter(|(x, y)| x != y)
+                .count()
+                == 1
+        }
+        1 => (0..long.len()).any(|i| long[..i].to_string() + &long[i + 1..] == short),
+        _ => false,
+    }
+}
+
+// How the codes of two characters are alike, e.g. "same code", or empty.
+pub fn likeness(db: &CJDatabase, a: &str, b: &str) -> &'static str {
+    let (x, y) = (db.codes_of(a), db.codes_of(b));
+    if x.iter().any(|c| y.contains(c)) {
+        "same code"
+    } else if x.iter().any(|c| y.iter().any(|d| one_key_apart(c, d))) {
+        "one key apart"
+    } else {
+        ""
+    }
+}
+
+// Return the pairs of characters sharing a code, each character with
+// the next one of the code.
+pub fn sharing_pairs(db: &CJDatabase) -> Vec<(String, String)> {
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    for x in db.v.iter().filter(|x| !x.code.is_empty()) {
+        let same = db.find_code(&x.code);
+        // once for each code
+        if !std::ptr::eq(same[0], x) {
+            continue;
+        }
+        for y in same.windows(2).filter(|y| y[0].char != y[1].char) {
+            pairs.push((y[0].char.clone(), y[1].char.clone()));
+        }
+    }
+    pairs
+}
+
+// Return the characters mixed up the most back to back, one pair after
+// another, topped up with pairs of characters sharing a code.
+pub fn pairs(db: &CJDatabase, confusions: &[Confusion], item_count: usize) -> Vec<Chinese> {
+    let mut sharing = sharing_pairs(db);
+    sharing.shuffle(&mut thread_rng());
+    let pairs = confusions.iter().map(|x| x.pair.clone()).chain(sharing);
+
+    let mut items: Vec<Chinese> = Vec::new();
+    for (a, b) in pairs {
+        if items.len() >= item_count {
+            break;
+        }
+        if let (Some(a), Some(b)) = (db.find_char(&a).first(), db.find_char(&b).first()) {
+            items.push((*a).clone());
+            items.push((*b).clone());
+        }
+    }
+    items.truncate(item_count);
+    items
+}
+
+// Drill the pairs(), those of the history of a user.  The history is read
+// for each round, so the pairs follow the answers of the last rounds.
+#[derive(Default)]
+pub struct Confusions {
+    pub history: String,      // history file, see history::history_path()
+    pub user: Option<String>, // whose answers, None if no profile
+}
+
+impl SelectionStrategy for Confusions {
+    fn name(&self) -> &'static str {
+        "confusions"
+    }
+
+    fn description(&self) -> &'static str {
+        "characters often mixed up, in pairs, see cj confusions"
+    }
+
+    fn select(&self, db: &CJDatabase, item_count: usize) -> Vec<Chinese> {
+        let answers = history::load(&self.history, Some(self.user.as_deref().unwrap_or("")));
+        let mut items = pairs(db, &tally(db, &answers), item_count);
+        // not enough pairs, e.g. a small data file, the default selection
+        // fills up the round
+        let quota = item_count.saturating_sub(items.len());
+        let rest: Vec<Chinese> = Smart::default()
+            .select(db, item_count)
+            .into_iter()
+            .filter(|x| !items.contains(x))
+            .take(quota)
+            .collect();
+        items.extend(rest);
+        items
+    }
+}
+
+#[cfg(test)]
+fn answer(time: u64, mode: &str, char: &str, typed: &str) -> Answer {
+    Answer {
+        time,
+        user: String::new(),
+        mode: mode.to_string(),
+        code: String::new(),
+        char: char.to_string(),
+        correct: false,
+        millis: 1500,
+        typed: typed.to_string(),
+    }
+}
+
+#[test]
+fn test_tally() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj05.csv");
+    let answers = vec![
+        answer(1, "code", "暈", "abjj"), // 暉 has the same code
+        answer(2, "char", "暉", "暈"),
+        answer(3, "code", "題", "aombc"), // its own code, but wrong
+        answer(4, "reverse", "題", "顥"),
+        answer(5, "quick", "題", "ac"),
+        answer(6, "code", "題", "zzzzz"), // no such code
+    ];
+    assert_eq!(confused_with(&db, &answers[0]), vec!["暉"]);
+    assert!(confused_with(&db, &answers[2]).is_empty());
+    assert!(confused_with(&db, &answers[4]).is_empty());
+
+    let found = tally(&db, &answers);
+    assert_eq!(
+        found,
+        vec![
+            Confusion {
+                pair: ("暈".to_string(), "暉".to_string()),
+                count: 2,
+                last: 2
+            },
+            Confusion {
+                pair: ("題".to_string(), "顥".to_string()),
+                count: 1,
+                last: 4
+            },
+        ]
+    );
+    assert_eq!(likeness(&db, "暈", "暉"), "same code");
+
+    let items = pairs(&db, &found, 6);
+    let chars: Vec<&str> = items.iter().map(|x| x.char.as_str()).collect();
+    assert_eq!(chars[..4], ["暈", "暉", "題", "顥"]);
+    assert_eq!(items.len(), 6);
+    assert_eq!(items[4].code, items[5].code);
+}
+
+#[test]
+fn test_one_key_apart() {
+    assert!(one_key_apart("ni", "nii"));
+    assert!(one_key_apart("sip", "sap"));
+    assert!(one_key_apart("abc", "ac"));
+    assert!(!one_key_apart("ni", "ni"));
+    assert!(!one_key_apart("ni", "niii"));
+    assert!(!one_key_apart("abc", "bca"));
+}
@@ -3885,6 +3885,7 @@ hhfbr,躺,0
 hhffs,軂,0
 hhgr,筶,0
 hhgu,筅,0
+hhh/5,身,0
 hhhaf,鵹,0
 hhhj,箄,0
 hhhnd,躲,0
@@ -4437,7 +4438,7 @@ hwtj,篳,0
 hx,臼,0
 hxbc,興,0
 hxbt,盥,0
-hxh,身,0
+hxh/3,身,0
 hxhu,兒,0
 hxjc,輿,0
 hxlb,帠,0
@@ -13102,3 +13103,78 @@ zxbv,》,0
 zxcd,「,0
 zxce,」,0
 ";
+
+// Components of some common characters and the part of the cangjie code
+// each one encodes, see radical::Components.
+pub static COMPONENTS: &str = "
+明,日:a,月:b
+題,是:ao,頁:mbc
+林,木:d,木:d
+森,木:d,木:d,木:d
+昌,日:a,日:a
+晶,日:a,日:a,日:a
+朋,月:b,月:b
+炎,火:f,火:f
+品,口:r,口:r,口:r
+好,女:v,子:nd
+你,人:o,尔:nf
+他,人:o,也:pd
+們,人:o,門:an
+休,人:o,木:d
+信,人:o,言:ymr
+語,言:yr,吾:mmr
+請,言:yr,青:qmb
+謝,言:yr,身:hh,寸:i
+時,日:a,寺:gdi
+晴,日:a,青:qmb
+清,水:e,青:qmb
+情,心:p,青:qmb
+江,水:e,工:m
+河,水:e,可:mnr
+海,水:e,每:owy
+湖,水:e,古:jr,月:b
+想,相:du,心:p
+和,禾:hd,口:r
+李,木:d,子:nd
+字,宀:j,子:nd
+男,田:w,力:ks
+動,重:hg,力:ks
+吃,口:r,乞:on
+唱,口:r,昌:aa
+姐,女:v,且:bm
+妹,女:v,未:jd
+始,女:v,台:ir
+間,門:an,日:a
+問,門:an,口:r
+聞,門:an,耳:sj
+國,囗:w,或:irm
+";
+
+// Built-in passages to type, title and text.
+pub static PASSAGES: [(&str, &str); 5] = [
+    (
+        "靜夜思 李白",
+        "床前明月光，疑是地上霜。
+舉頭望明月，低頭思故鄉。",
+    ),
+    (
+        "春曉 孟浩然",
+        "春眠不覺曉，處處聞啼鳥。
+夜來風雨聲，花落知多少。",
+    ),
+    (
+        "登鸛雀樓 王之渙",
+        "白日依山盡，黃河入海流。
+欲窮千里目，更上一層樓。",
+    ),
+    (
+        "千字文",
+        "天地玄黃，宇宙洪荒。日月盈昃，辰宿列張。
+寒來暑往，秋收冬藏。閏餘成歲，律呂調陽。",
+    ),
+    (
+        "論語 學而",
+        "學而時習之，不亦說乎？有朋自遠方來，不亦樂乎？
+人不知而不慍，不亦君子乎？",
+    ),
+];
@@ -1,27 +1,287 @@
+use std::collections::HashMap;
+use std::fmt;
 use std::fs::{self, File};
 use std::io::{self, BufRead, Write};
 use std::path::Path;
 
 use rand::seq::{IteratorRandom, SliceRandom};
 use rand::thread_rng;
+use serde::Deserialize;
 
 use super::data::PRISTINE;
+use super::schedule::{self, DEFAULT_EASE};
 
-const DATA_FILE: &str = r"./data/cj.csv";
-const TEMP_FILE: &str = r"./data/cjtemp.csv";
+pub const DATA_FILE: &str = r"./data/cj.csv";
 
 #[derive(Clone, Debug, PartialEq)]
 pub struct Chinese {
     pub char: String,
     pub code: String,
     pub rating: i16,
+    pub ease: f32,        // SM-2 ease factor
+    pub interval: u32,    // days until the next review
+    pub due: u64,         // next review in seconds since the unix epoch, 0 = never tested
+    pub times: Vec<u32>,  // recent response times of correct answers in ms, newest last
+    pub version: Version, // cangjie versions the code is valid for
+}
+
+// The Cangjie versions a code is valid for.  Most codes are the same in
+// Cangjie 3 and 5, those of one version only are marked after the code
+// in the data file, e.g. "hqi/5".
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Version {
+    #[default]
+    Any,
+    Only(u8), // 3 or 5
+}
+
+pub const VERSIONS: [u8; 2] = [3, 5]; // cangjie versions told apart
+
+impl Version {
+    // Return true if the code is valid in a version, None for any version.
+    pub fn accepts(self, version: Option<u8>) -> bool {
+        match (self, version) {
+            (Version::Only(v), Some(w)) => v == w,
+            _ => true,
+        }
+    }
+
+    // Mark after the code in the data file, e.g. "/5".
+    pub fn suffix(self) -> String {
+        match self {
+            Version::Any => String::new(),
+            Version::Only(v) => format!("/{}", v),
+        }
+    }
+}
+
+impl Chinese {
+    // Create a never tested record.
+    pub fn new(code: &str, char: &str) -> Self {
+        Chinese {
+            char: char.to_string(),
+            code: code.to_string(),
+            ..Default::default()
+        }
+    }
+
+    // Return a never tested copy of the record.
+    pub fn fresh(&self) -> Self {
+        Chinese {
+            version: self.version,
+            ..Chinese::new(&self.code, &self.char)
+        }
+    }
+
+    // Return true if the record has been tested.
+    pub fn is_tested(&self) -> bool {
+        self.rating != 0 || self.due > 0
+    }
+
+    // Median of the recent response times, None if never answered correctly.
+    pub fn median(&self) -> Option<u32> {
+        let mut times = self.times.clone();
+        times.sort_unstable();
+        match times.len() {
+            0 => None,
+            n if n % 2 == 1 => Some(times[n / 2]),
+            n => Some((times[n / 2 - 1] + times[n / 2]) / 2),
+        }
+    }
+
+    // The line of the record in the data file, see parse_line().
+    pub fn to_line(&self) -> String {
+        // keep never tested characters in the short format
+        let code = format!("{}{}", self.code, self.version.suffix());
+        let mut s = if self.due == 0 {
+            format!("{},{},{}", code, self.char, self.rating)
+        } else {
+            format!(
+                "{},{},{},{:.2},{},{}",
+                code, self.char, self.rating, self.ease, self.interval, self.due
+            )
+        };
+        if self.due > 0 && !self.times.is_empty() {
+            let times: Vec<String> = self.times.iter().map(|t| t.to_string()).collect();
+            s += &format!(",{}", times.join(" "));
+        }
+        s
+    }
+}
+
+impl Default for Chinese {
+    fn default() -> Self {
+        Chinese {
+            char: String::new(),
+            code: String::new(),
+            rating: 0,
+            ease: DEFAULT_EASE,
+            interval: 0,
+            due: 0,
+            times: Vec::new(),
+            version: Version::Any,
+        }
+    }
+}
+
+// A problem found in a line of the data file.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Problem {
+    Comment,       // line starts with #
+    MissingRating, // only code and char, rating set to 0
+    TooFewFields,  // not even code and char
+    BadRating,     // rating is not an integer, set to 0
+    BadSchedule,   // scheduling columns unreadable, reset
+    BadTimes,      // response times unreadable, reset
+    InvalidCode,   // code is not 1 to 5 letters
+    BadVersion,    // unknown cangjie version after the code, ignored
+    MultiChar,     // char field is not a single character
+    NotPhrase,     // phrase of less than two characters in the phrase file
+    NotUtf8,       // line is not valid UTF-8
+}
+
+impl Problem {
+    // A problem a save does not destroy: comment lines are saved as they
+    // are, and a missing rating is saved as 0, the same meaning.
+    pub fn is_harmless(&self) -> bool {
+        matches!(self, Problem::Comment | Problem::MissingRating)
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self {
+            Problem::Comment => "comment line",
+            Problem::MissingRating => "missing rating",
+            Problem::TooFewFields => "too few fields",
+            Problem::BadRating => "bad rating",
+            Problem::BadSchedule => "bad review schedule",
+            Problem::BadTimes => "bad response times",
+            Problem::InvalidCode => "invalid code letters",
+            Problem::BadVersion => "unknown cangjie version",
+            Problem::MultiChar => "not a single character",
+            Problem::NotPhrase => "not a phrase of two or more characters",
+            Problem::NotUtf8 => "not valid UTF-8",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+// A line of the data file with a problem.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadIssue {
+    pub line: usize,  // line number, from 1
+    pub text: String, // the line as in the file
+    pub problem: Problem,
+    pub kept: bool, // true if the record is loaded with a fix, false if skipped
+}
+
+// Quotas of get_items_smart(), as fractions of the round.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmartWeights {
+    pub due: f32,       // due for review
+    pub difficult: f32, // rating < 0
+    pub shaky: f32,     // correct but slow, see schedule::is_shaky()
+    pub new: f32,       // rating = 0
+}
+
+impl Default for SmartWeights {
+    fn default() -> Self {
+        SmartWeights {
+            due: 0.5,
+            difficult: 0.1,
+            shaky: 0.2,
+            new: 0.8,
+        }
+    }
 }
 
 pub struct CJDatabase {
     pub v: Vec<Chinese>,
+    pub path: String,           // data file
+    pub issues: Vec<LoadIssue>, // problems found when loading
+    // Lines saved as they are, at the top: comments, and phrases with a
+    // character not in the data file, see phrase::open().
+    pub kept_lines: Vec<String>,
+    // Progress file of a user profile.  When set, ratings are saved to the
+    // progress file, and the data file keeps the ratings in base.
+    pub progress: Option<String>,
+    // A phrase file, see phrase.rs: the records are phrases of two or
+    // more characters, and there is no pristine data.
+    pub phrases: bool,
+    base: HashMap<(String, String), Chinese>,
+    // Positions of the records in v, rebuilt whenever records are added,
+    // removed or moved.  Records are identified by code+char.
+    index: HashMap<(String, String), usize>,
+    by_code: HashMap<String, Vec<usize>>,
+    by_char: HashMap<String, Vec<usize>>,
+}
+
+impl Default for CJDatabase {
+    fn default() -> Self {
+        CJDatabase::new(DATA_FILE)
+    }
 }
 
 impl CJDatabase {
+    // Create an empty database for a data file.
+    pub fn new(path: &str) -> Self {
+        CJDatabase {
+            v: Vec::new(),
+            path: path.to_string(),
+            issues: Vec::new(),
+            kept_lines: Vec::new(),
+            progress: None,
+            phrases: false,
+            base: HashMap::new(),
+            index: HashMap::new(),
+            by_code: HashMap::new(),
+            by_char: HashMap::new(),
+        }
+    }
+
+    // Load, sort, dedup and save the data file, ready for use.
+    // If the data file has problems a save would destroy, it is not
+    // saved, so the lines in question are kept for the user to fix,
+    // see cj doctor.
+    pub fn open(path: &str) -> Self {
+        let mut db = CJDatabase::new(path);
+        db.load();
+        db.sort();
+        db.dedup();
+        match db.blocking() {
+            0 => db.save_as(path),
+            n => eprintln!(
+                "Problems found in {}: {}, run cj doctor for details",
+                path, n
+            ),
+        }
+        db
+    }
+
+    // Number of problems a save would destroy, see Problem::is_harmless().
+    fn blocking(&self) -> usize {
+        self.issues
+            .iter()
+            .filter(|x| !x.problem.is_harmless())
+            .count()
+    }
+
+    // Return an error if the data file has problems a save would destroy.
+    // Saving would drop or change the lines in question, so it is refused
+    // until they are fixed, see cj doctor --fix.
+    pub fn check(&self) -> Result<(), String> {
+        if self.blocking() == 0 {
+            return Ok(());
+        }
+        Err(format!(
+            "{} has problems, it is not saved until they are fixed: \
+             run cj doctor for details and cj doctor --fix to fix them",
+            self.path
+        ))
+    }
+
     fn read_lines<P>(&self, filename: P) -> io::Result<io::Lines<io::BufReader<File>>>
     where
         P: AsRef<Path>,
@@ -31,37 +291,66 @@ impl CJDatabase {
     }
 
     pub fn load(&mut self) {
-        self.load_from(DATA_FILE);
+        let path = self.path.clone();
+        self.load_from(&path);
     }
 
-    fn load_from(&mut self, filepath: &str) {
+    pub fn load_from(&mut self, filepath: &str) {
         // if filepath does not exist, create it with pristine data.
-        if !Path::new(filepath).exists() {
+        if !Path::new(filepath).exists() && !self.phrases {
             self.create_datafile(filepath);
         }
 
         if let Ok(lines) = self.read_lines(filepath) {
-            for line in lines {
-                if let Ok(buf) = line {
-                    let parts: Vec<&str> = buf.split(",").collect();
-                    if parts.len() >= 3 {
-                        let code = parts[0].trim().to_string().to_lowercase();
-                        let char = parts[1].trim().to_string();
-                        let score = parts[2].trim().parse::<i16>().unwrap();
-                        self.v.push(Chinese {
-                            char: char,
-                            code: code,
-                            rating: score,
+            for (i, line) in lines.enumerate() {
+                // a line which is not valid UTF-8 is skipped, not the rest
+                let buf = match line {
+                    Ok(buf) => buf,
+                    Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                        self.issues.push(LoadIssue {
+                            line: i + 1,
+                            text: String::new(),
+                            problem: Problem::NotUtf8,
+                            kept: false,
                         });
+                        continue;
                     }
+                    Err(e) => {
+                        eprintln!("{}:{}: {}", filepath, i + 1, e);
+                        break;
+                    }
+                };
+                let (ch, problem) = self.parse(&buf);
+                if problem == Some(Problem::Comment) {
+                    self.kept_lines.push(buf.clone());
+                }
+                if let Some(problem) = problem {
+                    self.issues.push(LoadIssue {
+                        line: i + 1,
+                        text: buf.clone(),
+                        problem,
+                        kept: ch.is_some(),
+                    });
+                }
+                if let Some(ch) = ch {
+                    self.v.push(ch);
                 }
             }
         }
-        println!("Records loaded: {}", self.v.len());
+        self.reindex();
+        eprintln!("Records loaded: {}", self.v.len());
+    }
+
+    // Parse a line of the data file, or of the phrase file.
+    fn parse(&self, buf: &str) -> (Option<Chinese>, Option<Problem>) {
+        match self.phrases {
+            true => parse_phrase(buf),
+            false => parse_line(buf),
+        }
     }
 
     fn create_datafile(&mut self, filepath: &str) {
-        println!("Creating database:  {}", filepath);
+        eprintln!("Creating database:  {}", filepath);
 
         let path = Path::new(filepath);
         let display = path.display();
@@ -71,7 +360,7 @@ impl CJDatabase {
         fs::create_dir_all(parent).unwrap();
 
         // Open a file in write-only mode, returns `io::Result<File>`
-        let mut file = match File::create(&path) {
+        let mut file = match File::create(path) {
             Err(why) => panic!("couldn't create {}: {}", display, why),
             Ok(file) => file,
         };
@@ -79,47 +368,117 @@ impl CJDatabase {
         // Write the pristine data to `file`, returns `io::Result<()>`
         match file.write_all(PRISTINE.as_bytes()) {
             Err(why) => panic!("couldn't write to {}: {}", display, why),
-            Ok(_) => println!("successfully wrote to {}", display),
+            Ok(_) => eprintln!("successfully wrote to {}", display),
         }
     }
 
-    // Save the current database with the default filename.
-    pub fn save(&mut self) {
-        self.save_as(DATA_FILE);
+    // Save the current database to its data file.
+    // With a user profile, save the ratings to the progress file and
+    // the records with their own ratings to the data file.
+    // A data file with problems is not saved, see check().
+    pub fn save(&mut self) -> Result<(), String> {
+        self.check()?;
+        let path = self.path.clone();
+        match &self.progress {
+            None => self.save_as(&path),
+            Some(progress) => {
+                write_records(progress, &[], self.v.iter().filter(|x| x.is_tested()));
+                let records: Vec<Chinese> = self
+                    .v
+                    .iter()
+                    .map(|x| match self.base.get(&(x.code.clone(), x.char.clone())) {
+                        Some(b) => b.clone(),
+                        None => x.fresh(),
+                    })
+                    .collect();
+                write_records(&path, &self.kept_lines, records.iter());
+            }
+        }
+        Ok(())
     }
 
     // Save the current database to disk in a safe way.
     pub fn save_as(&mut self, filepath: &str) {
-        // save to a temp file
-        let mut file = File::create(TEMP_FILE).expect("create failed");
-        for x in &self.v {
-            let s = format!("{},{},{}\n", x.code, x.char, x.rating);
-            file.write(s.as_bytes()).expect("data file write failed");
-        }
+        write_records(filepath, &self.kept_lines, self.v.iter());
+    }
 
-        // delete original file
-        if std::path::Path::new(filepath).exists() {
-            fs::remove_file(filepath).expect("unable to remove old data file");
+    // Keep the ratings in a progress file, for a user profile or the
+    // quick codes.
+    // The ratings of the data file are put aside, the records start as
+    // never tested, and the ratings in the progress file are applied.
+    // Switching from one progress file to another, the ratings of the
+    // data file stay aside.
+    pub fn use_progress(&mut self, progress: &str) {
+        if self.progress.is_none() {
+            self.base.clear();
+            for x in self.v.iter_mut() {
+                let fresh = x.fresh();
+                self.base.insert(
+                    (x.code.clone(), x.char.clone()),
+                    std::mem::replace(x, fresh),
+                );
+            }
+        } else {
+            for x in self.v.iter_mut() {
+                *x = x.fresh();
+            }
         }
+        self.progress = Some(progress.to_string());
 
-        // rename temp file to original file
-        fs::rename(TEMP_FILE, filepath).expect("unable to rename data file")
+        let mut count = 0;
+        if let Ok(lines) = self.read_lines(progress) {
+            for (i, line) in lines.enumerate() {
+                let buf = match line {
+                    Ok(buf) => buf,
+                    Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+                        eprintln!("{}:{}: skipped, {}", progress, i + 1, e);
+                        continue;
+                    }
+                    Err(e) => {
+                        eprintln!("{}:{}: {}", progress, i + 1, e);
+                        break;
+                    }
+                };
+                match self.parse(&buf) {
+                    (Some(mut y), None) => {
+                        if let Some(&index) = self.index.get(&(y.code.clone(), y.char.clone())) {
+                            // the data file tells the versions of the code
+                            y.version = self.v[index].version;
+                            self.v[index] = y;
+                            count += 1;
+                        }
+                    }
+                    (_, Some(problem)) => {
+                        eprintln!("{}:{}: skipped, {}: {}", progress, i + 1, problem, buf)
+                    }
+                    (None, None) => (),
+                }
+            }
+        }
+        eprintln!("Progress loaded: {} ({} records)", progress, count);
+    }
+    // Go back to the ratings of the data file, see use_progress().
+    pub fn drop_progress(&mut self) {
+        if self.progress.take().is_none() {
+            return;
+        }
+        for x in self.v.iter_mut() {
+            *x = match self.base.remove(&(x.code.clone(), x.char.clone())) {
+                Some(b) => b,
+                None => x.fresh(),
+            };
+        }
+        self.base.clear();
     }
 
     // Given a set of chinese characters, return a random subset of it.
     // This implementation allows duplicates in the subset.
-    #[allow(dead_code)]
     pub fn get_items_random(&self, item_count: i32) -> Vec<Chinese> {
-        let mut q = Vec::new();
-
-        let mut count = 0;
-        while count < item_count {
-            count = count + 1;
-            let question = self.v.choose(&mut thread_rng()).unwrap();
-            let c = question.clone();
-            q.push(c);
-        }
-        return q;
+        // none in an empty database
+        let mut rng = thread_rng();
+        (0..item_count)
+            .filter_map(|_| self.v.choose(&mut rng).cloned())
+            .collect()
     }
 
     // Given a set of chinese characters, return a subset of it
@@ -129,7 +488,6 @@ impl CJDatabase {
     // at most 33%: 0 < score <= 3 [easy]
     // the remaining: score > 3    [very easy]
     // rest                        [random]
-    #[allow(dead_code)]
     pub fn get_items_score(&self, item_count: usize) -> Vec<Chinese> {
         let mut items = Vec::new();
         let quota = item_count / 3; // 33%
@@ -200,22 +558,32 @@ impl CJDatabase {
     }
 
     // Return a subset of the chinese characters set for best user experience.
-    // Out of 10 chars:
+    // Out of 10 chars, with the default weights:
+    // Select up to 5 chars which are due for review, most overdue first
     // Select 1 char with rating < 0
     // Select 8 chars with rating = 0
     // Select chars with rating = 1, then rating = 2, ... until 3
     // Select rest of chars randomly
-    pub fn get_items_smart(&self, item_count: usize) -> Vec<Chinese> {
-        let mut items = Vec::new();
+    pub fn get_items_smart(&self, item_count: usize, weights: &SmartWeights) -> Vec<Chinese> {
+        let mut items: Vec<Chinese> = Vec::new();
         let mut rng = thread_rng();
         let mut quota;
+        let now = schedule::now();
+
+        // Select up to 5 chars which are due for review, most overdue first
+        quota = quota_of(item_count, weights.due);
+        let mut due: Vec<&Chinese> = self.v.iter().filter(|x| schedule::is_due(x, now)).collect();
+        due.sort_by_key(|x| x.due);
+        for q in due.into_iter().take(quota) {
+            items.push(q.clone());
+        }
 
         // Select 1 char with rating < 0
-        quota = item_count / 10;
+        quota = quota_of(item_count, weights.difficult);
         for q in self
             .v
             .iter()
-            .filter(|x| x.rating < 0)
+            .filter(|x| x.rating < 0 && !items.contains(x))
             .choose_multiple(&mut rng, quota)
         {
             items.push(q.clone());
@@ -224,8 +592,26 @@ impl CJDatabase {
             }
         }
 
+        // Select 2 chars which are answered correctly but slowly, slowest first
+        quota = quota_of(item_count, weights.shaky);
+        // the median response time once for each record, see is_shaky()
+        let mut shaky: Vec<(u32, &Chinese)> = self
+            .v
+            .iter()
+            .filter(|x| x.rating > 0 && !items.contains(x))
+            .map(|x| (schedule::slowness(x), x))
+            .filter(|(slowness, _)| *slowness > schedule::SHAKY)
+            .collect();
+        shaky.sort_by_key(|(slowness, _)| std::cmp::Reverse(*slowness));
+        for (_, q) in shaky.into_iter().take(quota) {
+            items.push(q.clone());
+            if items.len() >= item_count {
+                break;
+            }
+        }
+
         // Select 8 chars with rating = 0
-        quota = item_count * 8 / 10;
+        quota = quota_of(item_count, weights.new);
         for q in self
             .v
             .iter()
@@ -245,7 +631,7 @@ impl CJDatabase {
             for q in self
                 .v
                 .iter()
-                .filter(|x| x.rating == rating)
+                .filter(|x| x.rating == rating && !items.contains(x))
                 .choose_multiple(&mut rng, quota)
             {
                 items.push(q.clone());
@@ -274,74 +660,337 @@ impl CJDatabase {
         items
     }
 
-    // Update the database with the scores
-    pub fn update(&mut self, items: Vec<Chinese>) {
+    // Rebuild the indexes after the records are changed.
+    fn reindex(&mut self) {
+        self.index.clear();
+        self.by_code.clear();
+        self.by_char.clear();
+        for (i, x) in self.v.iter().enumerate() {
+            self.index.insert((x.code.clone(), x.char.clone()), i);
+            self.by_code.entry(x.code.clone()).or_default().push(i);
+            self.by_char.entry(x.char.clone()).or_default().push(i);
+        }
+    }
+
+    // Return the record of a code+char.
+    pub fn find(&self, code: &str, char: &str) -> Option<&Chinese> {
+        self.index
+            .get(&(code.to_string(), char.to_string()))
+            .map(|&i| &self.v[i])
+    }
+
+    // Return all the records of a chinese character.
+    pub fn find_char(&self, char: &str) -> Vec<&Chinese> {
+        self.by_char
+            .get(char)
+            .map(|x| x.iter().map(|&i| &self.v[i]).collect())
+            .unwrap_or_default()
+    }
+
+    // Return all the records of a code.
+    pub fn find_code(&self, code: &str) -> Vec<&Chinese> {
+        self.by_code
+            .get(code)
+            .map(|x| x.iter().map(|&i| &self.v[i]).collect())
+            .unwrap_or_default()
+    }
+
+    // Return the records of the codes starting with a prefix.
+    pub fn find_prefix(&self, prefix: &str) -> Vec<&Chinese> {
+        self.v
+            .iter()
+            .filter(|x| x.code.starts_with(prefix))
+            .collect()
+    }
+
+    // Return all the codes of a chinese character.
+    pub fn codes_of(&self, char: &str) -> Vec<String> {
+        self.find_char(char)
+            .into_iter()
+            .map(|x| x.code.clone())
+            .collect()
+    }
+
+    // Return the codes of a character valid in a cangjie version, and the
+    // codes of the other versions.  If none is valid, all of them are.
+    pub fn codes_in(&self, char: &str, version: Option<u8>) -> (Vec<String>, Vec<&Chinese>) {
+        if version.is_none() {
+            return (self.codes_of(char), Vec::new());
+        }
+        let (valid, others): (Vec<&Chinese>, Vec<&Chinese>) = self
+            .find_char(char)
+            .into_iter()
+            .partition(|x| x.version.accepts(version));
+        if valid.is_empty() {
+            return (others.iter().map(|x| x.code.clone()).collect(), Vec::new());
+        }
+        (valid.iter().map(|x| x.code.clone()).collect(), others)
+    }
+
+    // Return all the chinese characters of a code.
+    pub fn chars_of(&self, code: &str) -> Vec<String> {
+        self.find_code(code)
+            .into_iter()
+            .map(|x| x.char.clone())
+            .collect()
+    }
+
+    // Add the records not in the database yet, identified by code+char.
+    // Existing records keep their ratings.
+    // Return the number of records added.
+    pub fn merge(&mut self, items: Vec<Chinese>) -> usize {
+        let mut count = 0;
         for y in items {
-            let index = self.v.iter().position(|x| x.code == y.code).unwrap();
-            self.v[index].rating = y.rating;
+            let key = (y.code.clone(), y.char.clone());
+            if !self.index.contains_key(&key) {
+                self.index.insert(key, self.v.len());
+                self.v.push(y);
+                count += 1;
+            }
         }
+        self.sort();
+        self.dedup();
+        count
+    }
+
+    // Reset the ratings and review schedule of the records of a code,
+    // or of all records if no code is given.
+    // Return the number of records reset.
+    pub fn reset(&mut self, code: Option<&str>) -> usize {
+        let mut count = 0;
+        for x in self.v.iter_mut() {
+            if code.is_none_or(|c| x.code == c) {
+                *x = x.fresh();
+                count += 1;
+            }
+        }
+        count
+    }
+
+    // Update the database with the scores.
+    // Records are identified by code+char, unknown records are skipped.
+    // Return the number of records updated.
+    pub fn update(&mut self, items: Vec<Chinese>) -> usize {
+        let mut count = 0;
+        for y in items {
+            if let Some(&index) = self.index.get(&(y.code.clone(), y.char.clone())) {
+                self.v[index].rating = y.rating;
+                self.v[index].ease = y.ease;
+                self.v[index].interval = y.interval;
+                self.v[index].due = y.due;
+                self.v[index].times = y.times;
+                count += 1;
+            }
+        }
+        count
     }
 
     // Sort the database records by code
     pub fn sort(&mut self) {
         self.v.sort_by_key(|x| format!("{} {}", x.code, x.char));
-        println!("Records sorted")
+        self.reindex();
+        eprintln!("Records sorted")
     }
 
     // De-duplication the database records by code+char.
     // code+char because one code can represent multiple chars.
-    // Assume the records are already sorted by code+char.
+    // Keep the first record of a code+char, with the lowest rating.
     pub fn dedup(&mut self) {
         let mut counter = 0;
-        let mut last = Chinese {
-            code: "-1".to_string(),
-            char: "".to_string(),
-            rating: 0,
-        };
+        let mut seen: HashMap<(String, String), usize> = HashMap::new();
         let mut v2 = Vec::<Chinese>::new();
         for ch in self.v.iter() {
-            if ch.code == last.code && ch.char == last.char {
-                counter = counter + 1;
-                if ch.rating < last.rating {
-                    v2.pop();
-                    v2.push(Chinese {
-                        char: ch.char.clone(),
-                        code: ch.code.clone(),
-                        rating: ch.rating,
-                    });
-                    last.rating = ch.rating;
+            let key = (ch.code.clone(), ch.char.clone());
+            if let Some(&i) = seen.get(&key) {
+                counter += 1;
+                if ch.rating < v2[i].rating {
+                    v2[i] = ch.clone();
                 }
                 continue;
             }
-            v2.push(Chinese {
-                char: ch.char.clone(),
-                code: ch.code.clone(),
-                rating: ch.rating,
-            });
-            last.code = ch.code.clone();
-            last.char = ch.char.clone();
-            last.rating = ch.rating;
+            seen.insert(key, v2.len());
+            v2.push(ch.clone());
         }
         self.v = v2;
-        println!("Duplicates removed: {}", counter);
+        self.reindex();
+        eprintln!("Duplicates removed: {}", counter);
+    }
+}
+
+// Return a file in the same folder as the data file, e.g. the history
+// or a progress file.
+pub fn sibling(data: &str, name: &str) -> String {
+    Path::new(data)
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join(name)
+        .to_string_lossy()
+        .to_string()
+}
+
+// Save records to disk in a safe way.
+fn write_records<'a>(
+    filepath: &str,
+    kept_lines: &[String],
+    records: impl Iterator<Item = &'a Chinese>,
+) {
+    // the records are sorted, so the kept lines go first
+    let lines = kept_lines
+        .iter()
+        .cloned()
+        .chain(records.map(|x| x.to_line()));
+    write_lines(filepath, lines).expect("data file write failed");
+}
+
+// Write lines to a file in a safe way: the folder is created if need be,
+// and the lines go to a temp file next to the file, which then replaces
+// the file, so a failure part way leaves the file as it was.
+pub fn write_lines(filepath: &str, lines: impl Iterator<Item = String>) -> io::Result<()> {
+    if let Some(parent) = Path::new(filepath).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let tempfile = format!("{}.tmp", filepath);
+    let mut file = io::BufWriter::new(File::create(&tempfile)?);
+    for x in lines {
+        writeln!(file, "{}", x)?;
+    }
+    file.into_inner()?.sync_all()?;
+
+    // delete original file
+    if Path::new(filepath).exists() {
+        fs::remove_file(filepath)?;
+    }
+
+    // rename temp file to original file
+    fs::rename(tempfile, filepath)
+}
+
+// Parse a line of the data file: code,char,rating[,ease,interval,due]
+// Return the record, unless the line is blank or beyond repair,
+// and the problem found, if any.
+pub fn parse_line(buf: &str) -> (Option<Chinese>, Option<Problem>) {
+    parse_record(buf, false)
+}
+
+// Parse a line of the phrase file: codes,phrase,rating[,ease,...]
+// The codes are those of the characters separated by spaces, or a phrase
+// code of an input method, or left out to be filled in, see phrase.rs.
+pub fn parse_phrase(buf: &str) -> (Option<Chinese>, Option<Problem>) {
+    parse_record(buf, true)
+}
+
+fn parse_record(buf: &str, phrase: bool) -> (Option<Chinese>, Option<Problem>) {
+    let buf = buf.trim_start_matches('\u{feff}').trim();
+    if buf.is_empty() {
+        return (None, None);
+    }
+    if buf.starts_with('#') {
+        return (None, Some(Problem::Comment));
+    }
+
+    let parts: Vec<&str> = buf.split(',').map(|x| x.trim()).collect();
+    if parts.len() < 2 {
+        return (None, Some(Problem::TooFewFields));
+    }
+    // the code may be marked with the cangjie version it is valid for
+    let (code, version) = match parts[0].split_once('/') {
+        Some((code, version)) => (code.trim().to_lowercase(), parse_version(version)),
+        None => (parts[0].to_lowercase(), Some(Version::Any)),
+    };
+    let code = match phrase {
+        true => code.split_whitespace().collect::<Vec<&str>>().join(" "),
+        false => code,
+    };
+    let valid = match phrase {
+        true => code.split_whitespace().all(is_valid_code),
+        false => is_valid_code(&code),
+    };
+    if !valid {
+        return (None, Some(Problem::InvalidCode));
+    }
+    let char = parts[1].to_string();
+    match (phrase, char.chars().count()) {
+        (false, 1) => (),
+        (false, _) => return (None, Some(Problem::MultiChar)),
+        (true, n) if n < 2 => return (None, Some(Problem::NotPhrase)),
+        (true, _) => (),
+    }
+
+    let mut ch = Chinese {
+        char,
+        code,
+        version: version.unwrap_or_default(),
+        ..Default::default()
+    };
+    if parts.len() < 3 {
+        return (Some(ch), Some(Problem::MissingRating));
+    }
+    match parts[2].parse::<i16>() {
+        Ok(rating) => ch.rating = rating,
+        Err(_) => return (Some(ch), Some(Problem::BadRating)),
+    }
+
+    // scheduling columns, missing in older data files
+    if parts.len() > 3 {
+        let ease = parts[3].parse::<f32>();
+        let interval = parts.get(4).map(|x| x.parse::<u32>());
+        let due = parts.get(5).map(|x| x.parse::<u64>());
+        match (ease, interval, due) {
+            (Ok(ease), Some(Ok(interval)), Some(Ok(due))) if ease.is_finite() => {
+                ch.ease = ease;
+                ch.interval = interval;
+                ch.due = due;
+            }
+            _ => return (Some(ch), Some(Problem::BadSchedule)),
+        }
+    }
+
+    // response times, missing for characters never answered correctly
+    if let Some(times) = parts.get(6) {
+        match times.split_whitespace().map(|x| x.parse::<u32>()).collect() {
+            Ok(times) => ch.times = times,
+            Err(_) => return (Some(ch), Some(Problem::BadTimes)),
+        }
     }
+    match version {
+        Some(_) => (Some(ch), None),
+        None => (Some(ch), Some(Problem::BadVersion)),
+    }
+}
+
+// Parse the cangjie version marked after a code, None if unknown.
+pub fn parse_version(version: &str) -> Option<Version> {
+    match version.trim().parse::<u8>() {
+        Ok(v) if VERSIONS.contains(&v) => Some(Version::Only(v)),
+        _ => None,
+    }
+}
+
+// Return true if the code is made of 1 to 5 letters a to z.
+pub fn is_valid_code(code: &str) -> bool {
+    (1..=5).contains(&code.len()) && code.chars().all(|x| x.is_ascii_lowercase())
+}
+
+// Return the number of items for a fraction of the round.
+pub fn quota_of(item_count: usize, weight: f32) -> usize {
+    ((item_count as f32 * weight.clamp(0.0, 1.0)) as usize).min(item_count)
 }
 
 #[test]
 fn test_db_update() {
-    let mut db = CJDatabase { v: Vec::new() };
+    let mut db = CJDatabase::default();
     db.load_from("./tests/cj01.csv");
     let items = db.get_items_random(2);
     for i in &items {
         println!("{} {} {}", i.char, i.code, i.rating);
     }
     db.update(items);
-    db.save();
+    db.save().unwrap();
 }
 
 #[test]
 fn test_db_get_items_score() {
-    let mut db = CJDatabase { v: Vec::new() };
+    let mut db = CJDatabase::default();
     db.load_from("./tests/cj04.csv");
     let items = db.get_items_score(10);
     for (i, ch) in items.iter().enumerate() {
@@ -351,12 +1000,12 @@ fn test_db_get_items_score() {
 
 #[test]
 fn test_db_get_items_smart() {
-    let mut db = CJDatabase { v: Vec::new() };
+    let mut db = CJDatabase::default();
 
     for f in ["./tests/cj04.csv", "./tests/cj06.csv"] {
         println!("#> loading... {}", f);
         db.load_from(f);
-        let items = db.get_items_smart(10);
+        let items = db.get_items_smart(10, &SmartWeights::default());
         for (i, ch) in items.iter().enumerate() {
             println!("#{} {} {} {}", i, ch.char, ch.code, ch.rating);
         }
@@ -365,10 +1014,10 @@ fn test_db_get_items_smart() {
 
 #[test]
 fn test_db_sort() {
-    let mut db1 = CJDatabase { v: Vec::new() };
+    let mut db1 = CJDatabase::default();
     db1.load_from("./tests/cj02a.csv");
 
-    let mut db2 = CJDatabase { v: Vec::new() };
+    let mut db2 = CJDatabase::default();
     db2.load_from("./tests/cj02b.csv");
     db2.sort();
 
@@ -393,10 +1042,10 @@ fn test_db_dedup() {
     ];
 
     for (a, b) in data {
-        let mut db1 = CJDatabase { v: Vec::new() };
+        let mut db1 = CJDatabase::default();
         db1.load_from(a);
 
-        let mut db2 = CJDatabase { v: Vec::new() };
+        let mut db2 = CJDatabase::default();
         db2.load_from(b);
         db2.dedup();
         println!("{}", db2.v.len());
@@ -417,7 +1066,7 @@ fn test_db_dedup() {
 fn test_db_dedup_2() {
     let datafile = "./tests/cj03e.csv";
 
-    let mut db = CJDatabase { v: Vec::new() };
+    let mut db = CJDatabase::default();
     db.load_from(datafile);
     db.dedup();
 
@@ -448,7 +1097,7 @@ fn test_db_create_datafile() {
     let datafile0 = "./tests/cj05.csv";
     let parent = "./tests/temp";
     let datafile1 = format!("{}/{}", parent, "cj05_temp.csv");
-    let mut db = CJDatabase { v: Vec::new() };
+    let mut db = CJDatabase::default();
 
     if Path::new(parent).is_dir() {
         fs::remove_dir(parent).unwrap();
@@ -456,10 +1105,10 @@ fn test_db_create_datafile() {
 
     db.create_datafile(datafile1.as_str());
 
-    let mut db1 = CJDatabase { v: Vec::new() };
+    let mut db1 = CJDatabase::default();
     db1.load_from(datafile0);
 
-    let mut db2 = CJDatabase { v: Vec::new() };
+    let mut db2 = CJDatabase::default();
     db2.load_from(datafile1.as_str());
 
     let matched = db1
@@ -480,7 +1129,479 @@ fn test_char_clone() {
         char: "天".to_string(),
         code: "mk".to_string(),
         rating: 0,
+        ..Default::default()
     };
     let ch2 = ch1.clone();
     assert_eq!(ch1, ch2);
 }
+
+#[test]
+fn test_db_codes_of() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj06.csv");
+    assert_eq!(db.codes_of("暉"), vec!["abjj".to_string()]);
+    assert!(db.codes_of("X").is_empty());
+
+    db.merge(vec![Chinese {
+        char: "暉".to_string(),
+        code: "abjjx".to_string(),
+        rating: 0,
+        ..Default::default()
+    }]);
+    assert_eq!(db.codes_of("暉").len(), 2);
+}
+
+#[test]
+fn test_db_chars_of() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj06.csv");
+    db.merge(vec![Chinese {
+        char: "暈".to_string(),
+        code: "abjj".to_string(),
+        rating: 0,
+        ..Default::default()
+    }]);
+    let chars = db.chars_of("abjj");
+    assert_eq!(chars.len(), 2);
+    assert!(chars.contains(&"暉".to_string()));
+    assert!(chars.contains(&"暈".to_string()));
+    assert!(db.chars_of("zzzzz").is_empty());
+}
+
+#[test]
+// test loading data files with and without the scheduling columns
+fn test_db_load_schedule() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj07.csv");
+    assert_eq!(db.v.len(), 3);
+
+    assert_eq!(db.v[0].rating, 0);
+    assert_eq!(db.v[0].ease, DEFAULT_EASE);
+    assert_eq!(db.v[0].due, 0);
+
+    assert_eq!(db.v[1].rating, 2);
+    assert_eq!(db.v[1].ease, 2.6);
+    assert_eq!(db.v[1].interval, 6);
+    assert_eq!(db.v[1].due, 1700000000);
+    assert_eq!(db.v[1].times, vec![1800, 2400, 2100]);
+    assert_eq!(db.v[1].median(), Some(2100));
+    assert_eq!(db.v[2].times, vec![]);
+
+    let datafile = "./tests/cj07_temp.csv";
+    db.save_as(datafile);
+    let mut db2 = CJDatabase::default();
+    db2.load_from(datafile);
+    assert_eq!(db.v, db2.v);
+    fs::remove_file(datafile).unwrap();
+}
+
+#[test]
+// test a line which is not valid UTF-8 does not stop the loading
+fn test_db_load_not_utf8() {
+    let datafile = "./tests/cj13_temp.csv";
+    let mut bytes = b"aombc,\xe9\xa1,0\n".to_vec();
+    bytes.extend_from_slice(&fs::read("./tests/cj07.csv").unwrap());
+    fs::write(datafile, bytes).unwrap();
+
+    let mut db = CJDatabase::new(datafile);
+    db.load();
+    assert_eq!(db.v.len(), 3);
+    assert_eq!(db.issues.len(), 1);
+    assert_eq!(db.issues[0].line, 1);
+    assert_eq!(db.issues[0].problem, Problem::NotUtf8);
+
+    // the line would be lost
+    assert!(db.save().is_err());
+    assert!(fs::read(datafile)
+        .unwrap()
+        .starts_with(b"aombc,\xe9\xa1,0\n"));
+    fs::remove_file(datafile).unwrap();
+}
+
+#[test]
+// test comments and missing ratings do not stop a save, and the
+// comments are kept
+fn test_db_save_harmless_issues() {
+    let datafile = "./tests/cj14_temp.csv";
+    fs::write(datafile, "# my characters\nsip,忍\naombc,題,2\n").unwrap();
+
+    let mut db = CJDatabase::new(datafile);
+    db.load();
+    assert_eq!(db.issues.len(), 2);
+    assert!(db.check().is_ok());
+    db.save().unwrap();
+    assert_eq!(
+        fs::read_to_string(datafile).unwrap(),
+        "# my characters\nsip,忍,0\naombc,題,2\n"
+    );
+    fs::remove_file(datafile).unwrap();
+}
+
+#[test]
+// test overdue characters are picked first
+fn test_db_get_items_smart_due() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj06.csv");
+    for (i, ch) in db.v.iter_mut().take(3).enumerate() {
+        ch.rating = 5;
+        ch.due = 1 + i as u64;
+    }
+    let items = db.get_items_smart(10, &SmartWeights::default());
+    assert_eq!(items.len(), 10);
+    for ch in db.v.iter().take(3) {
+        assert!(items.contains(ch));
+    }
+}
+
+#[test]
+fn test_db_get_items_smart_shaky() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj06.csv");
+    for ch in db.v.iter_mut().take(3) {
+        ch.rating = 2;
+        ch.due = u64::MAX;
+    }
+    db.v[0].times = vec![9000, 9500];
+    db.v[1].times = vec![20000];
+    db.v[2].times = vec![500];
+
+    // the 2 shaky ones, slowest first
+    let weights = SmartWeights {
+        due: 0.0,
+        difficult: 0.0,
+        shaky: 0.2,
+        new: 0.0,
+    };
+    let items = db.get_items_smart(10, &weights);
+    assert!(items.contains(&db.v[0]));
+    assert!(items.contains(&db.v[1]));
+
+    let items = db.get_items_smart(5, &weights);
+    assert!(items.contains(&db.v[1]));
+}
+
+#[test]
+fn test_db_merge() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj03e.csv");
+    db.sort();
+    db.dedup();
+    let len = db.v.len();
+
+    let items = vec![
+        Chinese {
+            char: "題".to_string(),
+            code: "aombc".to_string(),
+            ..Default::default()
+        },
+        Chinese {
+            char: "明".to_string(),
+            code: "ab".to_string(),
+            ..Default::default()
+        },
+    ];
+    assert_eq!(db.merge(items), 1);
+    assert_eq!(db.v.len(), len + 1);
+
+    // existing record keeps its rating
+    let x = db.v.iter().find(|x| x.code == "aombc").unwrap();
+    assert_eq!(x.rating, -9);
+}
+
+#[test]
+fn test_db_reset() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj07.csv");
+
+    assert_eq!(db.reset(Some("ab")), 1);
+    let x = db.v.iter().find(|x| x.code == "ab").unwrap();
+    assert_eq!(x.rating, 0);
+    assert_eq!(x.due, 0);
+    assert_eq!(db.v.iter().filter(|x| x.due > 0).count(), 1);
+
+    assert_eq!(db.reset(None), 3);
+    assert!(db.v.iter().all(|x| x.rating == 0 && x.due == 0));
+}
+
+#[test]
+fn test_db_get_items_smart_weights() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj07.csv");
+    db.load_from("./tests/cj06.csv");
+    let weights = SmartWeights {
+        due: 0.0,
+        difficult: 0.0,
+        shaky: 0.0,
+        new: 1.0,
+    };
+    let items = db.get_items_smart(10, &weights);
+    assert!(items.iter().all(|x| x.rating == 0));
+}
+
+// Load the pristine database for tests.
+#[cfg(test)]
+fn load_pristine(name: &str) -> CJDatabase {
+    let datafile = format!("./tests/{}_pristine.csv", name);
+    let mut db = CJDatabase::new(&datafile);
+    db.create_datafile(&datafile);
+    db.load();
+    fs::remove_file(datafile).unwrap();
+    db
+}
+
+#[test]
+// test characters sharing a code are updated separately
+fn test_db_update_shared_code() {
+    let mut db = load_pristine("update_shared_code");
+    db.sort();
+    db.dedup();
+    for (code, chars) in [
+        ("abjj", ["暈", "暉"]),
+        ("ni", ["弘", "夕"]),
+        ("sip", ["忍", "慰"]),
+    ] {
+        assert!(db.chars_of(code).len() >= 2, "{}", code);
+
+        let mut ch = db.find(code, chars[1]).unwrap().clone();
+        ch.rating = 7;
+        assert_eq!(db.update(vec![ch]), 1);
+
+        assert_eq!(db.find(code, chars[0]).unwrap().rating, 0);
+        assert_eq!(db.find(code, chars[1]).unwrap().rating, 7);
+    }
+}
+
+#[test]
+// test updating the schedule and response times of a record
+fn test_db_update_schedule() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj06.csv");
+    let mut ch = db.find("aombc", "題").unwrap().clone();
+    schedule::review(&mut ch, schedule::PERFECT, 1700000000);
+    schedule::record_time(&mut ch, 2500);
+    assert_eq!(db.update(vec![ch.clone()]), 1);
+    assert_eq!(db.find("aombc", "題"), Some(&ch));
+}
+
+#[test]
+fn test_db_codes_in() {
+    let mut db = CJDatabase::default();
+    for line in ["hqi,我,0", "abc/3,我,0", "abd/5,我,0", "ab/3,明,0"] {
+        db.v.push(parse_line(line).0.unwrap());
+    }
+    db.reindex();
+
+    let (valid, others) = db.codes_in("我", Some(3));
+    assert_eq!(valid, vec!["hqi", "abc"]);
+    assert_eq!(others.len(), 1);
+    assert_eq!(others[0].code, "abd");
+    assert_eq!(db.codes_in("我", None).0, vec!["hqi", "abc", "abd"]);
+
+    // none valid in the version, take what there is
+    let (valid, others) = db.codes_in("明", Some(5));
+    assert_eq!(valid, vec!["ab"]);
+    assert!(others.is_empty());
+
+    // the versions are kept in the data file and through a profile
+    let datafile = "./tests/cj11_temp.csv";
+    db.save_as(datafile);
+    assert!(fs::read_to_string(datafile).unwrap().contains("abd/5,我,0"));
+    let mut db2 = CJDatabase::new(datafile);
+    db2.load();
+    db2.use_progress("./tests/cj11_temp_missing.csv");
+    assert_eq!(db2.find("abd", "我").unwrap().version, Version::Only(5));
+    fs::remove_file(datafile).unwrap();
+}
+
+#[test]
+// test the data which comes with the program tells the versions apart
+fn test_db_pristine_versions() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj05.csv");
+    let (valid, others) = db.codes_in("身", Some(3));
+    assert_eq!(valid, vec!["hxh"]);
+    assert_eq!(others[0].code, "hhh");
+    assert_eq!(db.codes_in("身", Some(5)).0, vec!["hhh"]);
+}
+
+#[test]
+// test updating a record not in the database
+fn test_db_update_missing() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj06.csv");
+    let ch = Chinese {
+        char: "題".to_string(),
+        code: "zzzzz".to_string(),
+        rating: 3,
+        ..Default::default()
+    };
+    assert_eq!(db.update(vec![ch]), 0);
+    assert!(db.find("zzzzz", "題").is_none());
+}
+
+#[test]
+// test dedup does not need sorted records
+fn test_db_dedup_unsorted() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj03e.csv");
+    db.load_from("./tests/cj02b.csv");
+    db.dedup();
+
+    assert_eq!(db.find("aombc", "題").unwrap().rating, -9);
+    assert_eq!(db.find("cvmi", "鏘").unwrap().rating, -5);
+    assert_eq!(db.find("yonk", "夜").unwrap().rating, 0);
+    let mut keys: Vec<(&str, &str)> =
+        db.v.iter()
+            .map(|x| (x.code.as_str(), x.char.as_str()))
+            .collect();
+    let len = keys.len();
+    keys.sort();
+    keys.dedup();
+    assert_eq!(keys.len(), len);
+}
+
+#[test]
+fn test_is_valid_code() {
+    assert!(is_valid_code("a"));
+    assert!(is_valid_code("aombc"));
+    assert!(!is_valid_code(""));
+    assert!(!is_valid_code("aombcd"));
+    assert!(!is_valid_code("ab1"));
+    assert!(!is_valid_code("AB"));
+}
+
+#[test]
+fn test_parse_line() {
+    let (ch, problem) = parse_line(" AOMBC , 題 , -2 ");
+    let ch = ch.unwrap();
+    assert_eq!(
+        (ch.code.as_str(), ch.char.as_str(), ch.rating),
+        ("aombc", "題", -2)
+    );
+    assert_eq!(problem, None);
+
+    assert_eq!(parse_line(""), (None, None));
+    assert_eq!(parse_line("   "), (None, None));
+    assert_eq!(parse_line("# my notes").1, Some(Problem::Comment));
+    assert_eq!(parse_line("aombc").1, Some(Problem::TooFewFields));
+    assert_eq!(parse_line("a1,題,0").1, Some(Problem::InvalidCode));
+    assert_eq!(parse_line("aombcd,題,0").1, Some(Problem::InvalidCode));
+    assert_eq!(parse_line("ab,明天,0").1, Some(Problem::MultiChar));
+    assert_eq!(parse_line("ab,,0").1, Some(Problem::MultiChar));
+
+    let (ch, problem) = parse_line("ab,明");
+    assert_eq!(ch.unwrap().rating, 0);
+    assert_eq!(problem, Some(Problem::MissingRating));
+
+    let (ch, problem) = parse_line("ab,明,x");
+    assert_eq!(ch.unwrap().rating, 0);
+    assert_eq!(problem, Some(Problem::BadRating));
+
+    let (ch, problem) = parse_line("ab,明,2,2.6,6");
+    let ch = ch.unwrap();
+    assert_eq!((ch.rating, ch.due), (2, 0));
+    assert_eq!(problem, Some(Problem::BadSchedule));
+
+    let (ch, problem) = parse_line("ab,明,2,2.6,6,1700000000,1800 x");
+    let ch = ch.unwrap();
+    assert_eq!((ch.due, ch.times.len()), (1700000000, 0));
+    assert_eq!(problem, Some(Problem::BadTimes));
+
+    assert_eq!(parse_line("\u{feff}ab,明,0").1, None);
+
+    let (ch, problem) = parse_line("HQI/5,我,0");
+    let ch = ch.unwrap();
+    assert_eq!((ch.code.as_str(), ch.version), ("hqi", Version::Only(5)));
+    assert_eq!(problem, None);
+    let (ch, problem) = parse_line("hqi/4,我,0");
+    assert_eq!(ch.unwrap().version, Version::Any);
+    assert_eq!(problem, Some(Problem::BadVersion));
+    assert_eq!(parse_line("/5,我,0").1, Some(Problem::InvalidCode));
+}
+
+#[test]
+fn test_parse_phrase() {
+    let (ch, problem) = parse_phrase(" AB  MK ,明天,1");
+    let ch = ch.unwrap();
+    assert_eq!((ch.code.as_str(), ch.char.as_str()), ("ab mk", "明天"));
+    assert_eq!((ch.rating, problem), (1, None));
+
+    // the codes are filled in later
+    assert_eq!(parse_phrase(",明天,0").0.unwrap().code, "");
+    assert_eq!(parse_phrase("ab,明,0").1, Some(Problem::NotPhrase));
+    assert_eq!(
+        parse_phrase("ab mkzzzz,明天,0").1,
+        Some(Problem::InvalidCode)
+    );
+}
+
+#[test]
+// test the loader keeps going and reports line numbers
+fn test_db_load_issues() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj08.csv");
+
+    let found: Vec<(usize, Problem, bool)> = db
+        .issues
+        .iter()
+        .map(|x| (x.line, x.problem, x.kept))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            (1, Problem::Comment, false),
+            (3, Problem::BadRating, true),
+            (4, Problem::TooFewFields, false),
+            (5, Problem::InvalidCode, false),
+            (6, Problem::MultiChar, false),
+            (8, Problem::MissingRating, true),
+        ]
+    );
+    assert_eq!(db.issues[1].text, "ab,明,x");
+    assert_eq!(db.v.len(), 4);
+    assert_eq!(db.find("ab", "明").unwrap().rating, 0);
+}
+
+#[test]
+// test a profile keeps its ratings apart from the data file
+fn test_db_use_progress() {
+    let datafile = "./tests/cj09_temp.csv";
+    let progress = "./tests/cj09_temp_amy.csv";
+    fs::copy("./tests/cj07.csv", datafile).unwrap();
+
+    let mut db = CJDatabase::new(datafile);
+    db.load();
+    db.use_progress(progress);
+    assert!(db.v.iter().all(|x| !x.is_tested()));
+
+    let mut ch = db.find("aombc", "題").unwrap().clone();
+    ch.rating = 4;
+    ch.due = 1800000000;
+    db.update(vec![ch]);
+    db.save().unwrap();
+
+    // the data file keeps its own ratings
+    let mut db1 = CJDatabase::new(datafile);
+    db1.load();
+    assert_eq!(db1.find("aombc", "題").unwrap().rating, 0);
+    assert_eq!(db1.find("ab", "明").unwrap().rating, 2);
+
+    // the progress file has the profile ratings only
+    let mut db2 = CJDatabase::new(datafile);
+    db2.load();
+    db2.use_progress(progress);
+    assert_eq!(db2.find("aombc", "題").unwrap().rating, 4);
+    assert_eq!(db2.find("ab", "明").unwrap().rating, 0);
+    assert_eq!(fs::read_to_string(progress).unwrap().lines().count(), 1);
+
+    // switching to another progress file and back to the data file
+    db2.use_progress("./tests/cj09_temp_missing.csv");
+    assert!(db2.v.iter().all(|x| !x.is_tested()));
+    db2.drop_progress();
+    assert_eq!(db2.find("aombc", "題").unwrap().rating, 0);
+    assert_eq!(db2.find("ab", "明").unwrap().rating, 2);
+    assert_eq!(db2.progress, None);
+
+    fs::remove_file(datafile).unwrap();
+    fs::remove_file(progress).unwrap();
+}
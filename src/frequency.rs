@@ -0,0 +1,121 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+
+use super::database::{sibling, write_lines};
+
+pub const FREQUENCY_FILE: &str = "frequency.csv"; // character frequency ranks, optional
+
+// How common the characters are, 1 for the most frequent character.
+// The frequency file has a line for each character, most frequent first,
+// e.g. 1,的
+// It is made by cj frequency from a frequency list, see parse_list().
+#[derive(Clone, Debug, Default)]
+pub struct Frequency {
+    ranks: HashMap<String, usize>, // char => rank
+}
+
+impl Frequency {
+    // Create the ranks of characters, most frequent first.
+    pub fn new(chars: &[String]) -> Frequency {
+        let mut ranks = HashMap::new();
+        for (i, x) in chars.iter().enumerate() {
+            ranks.entry(x.clone()).or_insert(i + 1);
+        }
+        Frequency { ranks }
+    }
+
+    // Load the frequency file, no ranks if there is none.
+    pub fn load(path: &str) -> Frequency {
+        match fs::read_to_string(path) {
+            Ok(text) => Frequency::new(&parse_list(&text).0),
+            Err(_) => Frequency::default(),
+        }
+    }
+
+    // Rank of a character, None if it is not in the list.
+    pub fn rank(&self, char: &str) -> Option<usize> {
+        self.ranks.get(char).copied()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranks.is_empty()
+    }
+
+    // Return true if a character is among the top most frequent ones,
+    // any character if there is no limit.
+    pub fn in_top(&self, char: &str, top: Option<usize>) -> bool {
+        top.is_none_or(|n| self.rank(char).is_some_and(|r| r <= n))
+    }
+}
+
+// Read a frequency list, one character per line, most frequent first.
+// The character may come with other fields, e.g. a rank and a count:
+//   的
+//   1,的
+//   1	的	7922684	4.09
+// The first field of a single non-ASCII character is taken.
+// Return the characters in order and the number of lines skipped.
+pub fn parse_list(text: &str) -> (Vec<String>, usize) {
+    let mut chars: Vec<String> = Vec::new();
+    let mut seen = HashSet::new();
+    let mut skipped = 0;
+    for line in text
+        .lines()
+        .map(|x| x.trim_start_matches('\u{feff}').trim())
+    {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let found = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .find(|x| x.chars().count() == 1 && !x.is_ascii());
+        match found {
+            Some(x) if seen.insert(x) => chars.push(x.to_string()),
+            Some(_) => {}
+            None => skipped += 1,
+        }
+    }
+    (chars, skipped)
+}
+
+// Save the characters of a frequency list to the frequency file.
+pub fn save(path: &str, chars: &[String]) -> Result<(), String> {
+    let lines = chars
+        .iter()
+        .enumerate()
+        .map(|(i, x)| format!("{},{}", i + 1, x));
+    write_lines(path, lines).map_err(|e| format!("unable to write {}: {}", path, e))
+}
+
+// Return the frequency file next to the data file.
+pub fn frequency_path(data: &str) -> String {
+    sibling(data, FREQUENCY_FILE)
+}
+
+#[test]
+fn test_parse_list() {
+    let text = "# most frequent first\n的\n1,一\n3\t是\t123456\t2.1\n\n的\nthe\n";
+    let (chars, skipped) = parse_list(text);
+    assert_eq!(chars, vec!["的", "一", "是"]);
+    assert_eq!(skipped, 1);
+
+    let freq = Frequency::new(&chars);
+    assert_eq!(freq.rank("的"), Some(1));
+    assert_eq!(freq.rank("是"), Some(3));
+    assert_eq!(freq.rank("題"), None);
+    assert!(freq.in_top("一", Some(2)));
+    assert!(!freq.in_top("是", Some(2)));
+    assert!(!freq.in_top("題", Some(2)));
+    assert!(freq.in_top("題", None));
+}
+
+#[test]
+fn test_frequency_file() {
+    let path = "./tests/cj12_temp.csv";
+    let chars = vec!["的".to_string(), "一".to_string()];
+    save(path, &chars).unwrap();
+    let freq = Frequency::load(path);
+    fs::remove_file(path).unwrap();
+    assert_eq!(freq.rank("一"), Some(2));
+    assert!(Frequency::load("./tests/no_such_frequency.csv").is_empty());
+}
@@ -0,0 +1,919 @@
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use console::{Key, Term};
+use owo_colors::{OwoColorize, Stream};
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+use serde::Deserialize;
+
+use super::choice;
+use super::database::{quota_of, sibling, CJDatabase, Chinese, Version};
+use super::highscore::{self, Entry};
+use super::history::{self, Answer};
+use super::phrase::{self, PHRASE_SHARE};
+use super::profile;
+use super::quick;
+use super::radical::{self, Components};
+use super::schedule;
+use super::strategy::SelectionStrategy;
+
+pub const MAX_CODE_LEN: usize = 5; // longest cangjie code of a character
+pub const HINT_KEY: char = '?'; // reveal the next hint while answering
+
+// How the user answers a question.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    // Show the character, type the character with an input method.
+    Char,
+    // Show the character, type the cangjie letters.
+    Code,
+    // Show the cangjie code, type the character with an input method.
+    Reverse,
+    // Show the character, type the 速成 quick code letters.
+    Quick,
+    // Code entry with phrases mixed in, type the codes of each character
+    // of a phrase in a row.
+    Phrase,
+    // Show the character, pick its code among a few with keys 1 to 4.
+    Choice,
+}
+
+// How a game is played.
+pub struct Settings {
+    pub count: usize,         // number of questions in a round
+    pub mode: Mode,           // quiz mode of the first round
+    pub practice: bool,       // practice the character after a wrong answer
+    pub user: Option<String>, // user profile
+    pub cangjie: Option<u8>,  // cangjie version practised, None for any
+}
+
+impl Mode {
+    pub fn next(self) -> Mode {
+        match self {
+            Mode::Char => Mode::Code,
+            Mode::Code => Mode::Reverse,
+            Mode::Reverse => Mode::Quick,
+            Mode::Quick => Mode::Phrase,
+            Mode::Phrase => Mode::Choice,
+            Mode::Choice => Mode::Char,
+        }
+    }
+
+    // Name used on the command line, in the config and history files.
+    pub fn key(self) -> &'static str {
+        match self {
+            Mode::Char => "char",
+            Mode::Code => "code",
+            Mode::Reverse => "reverse",
+            Mode::Quick => "quick",
+            Mode::Phrase => "phrase",
+            Mode::Choice => "choice",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Mode::Char => "character",
+            Mode::Code => "code entry",
+            Mode::Reverse => "reverse",
+            Mode::Quick => "quick (速成)",
+            Mode::Phrase => "phrases (詞語)",
+            Mode::Choice => "multiple choice",
+        }
+    }
+
+    // Return true if the answer is typed in cangjie letters.
+    pub fn letters(self) -> bool {
+        matches!(self, Mode::Code | Mode::Quick | Mode::Phrase)
+    }
+}
+
+// A question of a round, as shown to the user.
+pub struct Question<'a> {
+    pub number: usize, // 1 to total, 0 when practising after a wrong answer
+    pub total: usize,  // number of questions in the round, 0 for no end
+    pub mode: Mode,
+    pub chin: &'a Chinese,
+    pub codes: &'a [String],   // all codes of the character
+    pub choices: &'a [String], // codes to pick from in choice mode, or empty
+    pub hints: &'a [String],   // revealed one at a time with HINT_KEY
+    pub context: &'a str,      // text around the character of a passage, or empty
+}
+
+// The answer to a question.
+pub struct Reply {
+    pub text: String, // what was typed
+    pub hints: usize, // number of hints revealed
+    pub ended: bool,  // no more input, e.g. end of file
+}
+
+impl Question<'_> {
+    // e.g. "#3/10 ", "#3 " without an end or "Practice:"
+    pub fn prompt(&self) -> String {
+        match (self.number, self.total) {
+            (0, _) => "Practice:".to_string(),
+            (n, 0) => format!("#{} ", n),
+            (n, total) => format!("#{}/{} ", n, total),
+        }
+    }
+}
+
+// Front end of the game, see LineUi and tui::Tui.
+pub trait Ui {
+    // Welcome the user.
+    fn banner(&mut self);
+    // Ask user to continue, switch the quiz mode or quit.
+    // Return the mode to play, or None to quit.
+    fn ask_continue(&mut self, mode: Mode) -> Option<Mode>;
+    // A round of total questions begins.
+    fn begin(&mut self, total: usize);
+    // Ask a question, return the answer.
+    // HINT_KEY reveals the next hint of the question.
+    fn ask(&mut self, question: &Question) -> Reply;
+    // Tell whether the answer was correct, with the score so far.
+    fn answered(&mut self, correct: bool, score: usize, message: &str);
+    // The round is over.
+    fn score(&mut self, score: usize, total: usize, time: Duration);
+    // Ask for the initials of a new high score.
+    fn ask_initials(&mut self, rank: usize) -> String;
+    // Show a high score board.
+    fn board(&mut self, entries: &[Entry], title: &str);
+    // Show a report after the score, e.g. the typing speed of a passage.
+    fn report(&mut self, text: &str);
+    // Count down to a deadline before the next question, e.g. the time
+    // left of an arcade game, None when there is none.
+    fn countdown(&mut self, deadline: Option<Instant>);
+}
+
+// Line by line front end, for any terminal.
+pub struct LineUi;
+
+impl Ui for LineUi {
+    fn banner(&mut self) {
+        show_banner();
+    }
+
+    fn ask_continue(&mut self, mode: Mode) -> Option<Mode> {
+        println!();
+        ask_continue(mode)
+    }
+
+    fn begin(&mut self, _total: usize) {
+        println!("\n======== C H A L L E N G E   B E G I N S ========");
+    }
+
+    fn ask(&mut self, question: &Question) -> Reply {
+        if question.number > 0 {
+            println!();
+            if !question.context.is_empty() {
+                println!("{}", question.context);
+            }
+        }
+        ask_mode(question)
+    }
+
+    fn answered(&mut self, _correct: bool, _score: usize, message: &str) {
+        println!("{}", message);
+    }
+
+    fn score(&mut self, score: usize, total: usize, time: Duration) {
+        show_score(score, total, time);
+    }
+
+    fn ask_initials(&mut self, rank: usize) -> String {
+        println!("*** NEW HIGH SCORE!  RANK {} ***", rank);
+        highscore::ask_initials()
+    }
+
+    fn board(&mut self, entries: &[Entry], title: &str) {
+        highscore::show(entries, title);
+    }
+
+    fn report(&mut self, text: &str) {
+        println!("{}", text);
+    }
+
+    fn countdown(&mut self, deadline: Option<Instant>) {
+        if let Some(deadline) = deadline {
+            let left = deadline.saturating_duration_since(Instant::now());
+            println!("Time left: {:.1} seconds", left.as_secs_f64());
+        }
+    }
+}
+
+// Ask user to enter a chinese char.
+// Return the answer.
+fn ask(prompt: &str, chinchar: &str, hints: &[String]) -> Reply {
+    println!("{}[{}]?", prompt, chinchar);
+    read_reply(hints)
+}
+
+// Read an answer line by line, showing a hint for each HINT_KEY line.
+fn read_reply(hints: &[String]) -> Reply {
+    let mut used = 0;
+    loop {
+        let mut line = String::new();
+        let ended = !matches!(std::io::stdin().read_line(&mut line), Ok(n) if n > 0);
+        if line.trim() == HINT_KEY.to_string() {
+            used = show_hint(hints, used);
+            continue;
+        }
+        return Reply {
+            text: line.trim().to_string(),
+            hints: used,
+            ended,
+        };
+    }
+}
+
+// Ask user to enter a chinese char for a cangjie code.
+// The code is shown in letters and in radicals.
+// Return the answer.
+fn ask_reverse(prompt: &str, code: &str, hints: &[String]) -> Reply {
+    ask(
+        prompt,
+        &format!("{} {}", code, radical::radicals(code)),
+        hints,
+    )
+}
+
+// Show the next hint if there is one, return the number of hints shown.
+fn show_hint(hints: &[String], used: usize) -> usize {
+    match hints.get(used) {
+        Some(hint) => {
+            println!(
+                "{}",
+                hint.if_supports_color(Stream::Stdout, |x| x.bright_cyan())
+            );
+            used + 1
+        }
+        None => {
+            println!("No more hints.");
+            used
+        }
+    }
+}
+
+// Ask user to type the cangjie code of a chinese char, one key at a time.
+// Each keystroke is shown in green while it is still on the way to one
+// of the valid codes, and in red from the first wrong letter onwards.
+// Return the typed code.
+fn ask_code(prompt: &str, chinchar: &str, codes: &[String], hints: &[String]) -> Reply {
+    let term = Term::stdout();
+
+    // no key by key input available, e.g. input is redirected
+    if !term.is_term() {
+        let reply = ask(prompt, chinchar, hints);
+        return Reply {
+            text: reply.text.to_lowercase(),
+            ..reply
+        };
+    }
+
+    println!("{}[{}]?", prompt, chinchar);
+    let mut typed = String::new();
+    let mut used = 0;
+    let mut ended = false;
+    loop {
+        match term.read_key() {
+            Ok(Key::Char(ch)) if ch.is_ascii_alphabetic() => {
+                if typed.len() < max_len(chinchar) {
+                    typed.push(ch.to_ascii_lowercase());
+                }
+            }
+            Ok(Key::Char(HINT_KEY)) => {
+                term.clear_line().unwrap();
+                used = show_hint(hints, used);
+            }
+            Ok(Key::Backspace) => {
+                typed.pop();
+            }
+            Ok(Key::Enter) | Ok(Key::Char(' ')) => {
+                if !typed.is_empty() {
+                    break;
+                }
+            }
+            Ok(_) => continue,
+            Err(_) => {
+                ended = true;
+                break;
+            }
+        }
+
+        let good = prefix_len(&typed, codes);
+        term.clear_line().unwrap();
+        let (ok, bad) = typed.split_at(good);
+        print!(
+            "{}{}",
+            ok.if_supports_color(Stream::Stdout, |x| x.bright_green()),
+            bad.if_supports_color(Stream::Stdout, |x| x.bright_red())
+        );
+        term.flush().unwrap();
+    }
+    println!();
+
+    Reply {
+        text: typed,
+        hints: used,
+        ended,
+    }
+}
+
+// Longest code typed for a character, or for a phrase.
+pub fn max_len(char: &str) -> usize {
+    MAX_CODE_LEN * char.chars().count().max(1)
+}
+
+// Ask the question of a quiz mode, return the answer.
+fn ask_mode(question: &Question) -> Reply {
+    let prompt = question.prompt();
+    let chin = question.chin;
+    match question.mode {
+        Mode::Char => ask(&prompt, &chin.char, question.hints),
+        Mode::Code | Mode::Quick | Mode::Phrase => {
+            ask_code(&prompt, &chin.char, question.codes, question.hints)
+        }
+        Mode::Reverse => ask_reverse(&prompt, &chin.code, question.hints),
+        Mode::Choice => ask_choice(&prompt, &chin.char, question.choices, question.hints),
+    }
+}
+
+// Ask user to pick the cangjie code of a chinese char among a few,
+// shown in letters and in radicals, with a key from 1.
+// Return the number of the code picked.
+fn ask_choice(prompt: &str, chinchar: &str, choices: &[String], hints: &[String]) -> Reply {
+    let term = Term::stdout();
+    let options: Vec<String> = choices
+        .iter()
+        .enumerate()
+        .map(|(i, x)| format!("  {}  {} {}", i + 1, x, radical::radicals(x)))
+        .collect();
+
+    println!("{}[{}]?\n{}", prompt, chinchar, options.join("\n"));
+    // no key by key input available, e.g. input is redirected
+    if !term.is_term() {
+        // only the number of a code is an answer, not the code itself
+        loop {
+            let reply = read_reply(hints);
+            if reply.ended || choice::picked(choices, &reply.text).is_some() {
+                return reply;
+            }
+            println!("Pick a code with a number from 1 to {}", choices.len());
+        }
+    }
+
+    let mut used = 0;
+    loop {
+        match term.read_key() {
+            Ok(Key::Char(HINT_KEY)) => used = show_hint(hints, used),
+            Ok(Key::Char(ch)) if choice::number(ch, choices.len()).is_some() => {
+                println!("{}", ch);
+                return Reply {
+                    text: ch.to_string(),
+                    hints: used,
+                    ended: false,
+                };
+            }
+            Ok(_) => continue,
+            Err(_) => {
+                return Reply {
+                    text: String::new(),
+                    hints: used,
+                    ended: true,
+                }
+            }
+        }
+    }
+}
+
+// Hints to the code of a character, from a little to all of it:
+// the number of keys, the first radical, then the whole code.
+// The character of the reverse mode has no hints, the whole code is
+// not given in choice mode.
+fn hints(mode: Mode, char: &str, full: &str, components: &Components) -> Vec<String> {
+    if mode == Mode::Reverse || full.is_empty() {
+        return Vec::new();
+    }
+    let code = match mode {
+        Mode::Quick => quick::quick_code(full),
+        _ => full.to_string(),
+    };
+    let len = code.len();
+    let blanks = |n: usize| vec!["_"; n].join(" ");
+    let keys = if len == 1 { "key" } else { "keys" };
+    let first = radical::radicals(&code[..1]);
+    let whole = radical::explain(char, full, components);
+    let mut hints = vec![
+        format!("Hint: {} {}  {}", len, keys, blanks(len)),
+        format!("Hint: {} {}", first, blanks(len - 1))
+            .trim()
+            .to_string(),
+        match mode {
+            Mode::Quick => format!("Hint: {} from {}", code, whole),
+            _ => format!("Hint: {}", whole),
+        },
+    ];
+    // the whole code would give away the answer to pick
+    if mode == Mode::Choice {
+        hints.pop();
+    }
+    hints
+}
+
+// Return true if the answer is correct in a quiz mode.
+// codes: all codes of the character, the quick codes in quick mode,
+// chars: all characters of the code
+fn is_correct(
+    mode: Mode,
+    answer: &str,
+    chin: &Chinese,
+    codes: &[String],
+    chars: &[String],
+) -> bool {
+    match mode {
+        Mode::Char => chin.char == answer,
+        Mode::Code | Mode::Quick | Mode::Phrase | Mode::Choice => check_code(answer, codes),
+        Mode::Reverse => chars.iter().any(|x| x == answer),
+    }
+}
+
+// Return the answer typed: the code picked by its number in choice mode,
+// nothing if no code was picked, or the text typed in the other modes.
+fn answer(mode: Mode, choices: &[String], typed: &str) -> String {
+    match mode {
+        Mode::Choice => choice::picked(choices, typed).unwrap_or_default(),
+        _ => typed.to_string(),
+    }
+}
+
+// Return true if the typed letters are one of the valid codes.
+fn check_code(typed: &str, codes: &[String]) -> bool {
+    codes.iter().any(|x| x == typed)
+}
+
+// Return the length of the longest leading part of the typed letters
+// which is still the beginning of one of the valid codes.
+pub fn prefix_len(typed: &str, codes: &[String]) -> usize {
+    (0..=typed.len())
+        .rev()
+        .find(|&n| codes.iter().any(|x| x.starts_with(&typed[..n])))
+        .unwrap_or(0)
+}
+
+// Outcome of a round of challenges.
+#[derive(Default)]
+pub struct Round {
+    pub items: Vec<Chinese>,  // rescheduled characters
+    pub correct: usize,       // number of correct answers
+    pub time: Duration,       // time taken
+    pub answers: Vec<Answer>, // for the history
+}
+
+impl Round {
+    // Return a character as last reviewed in the round, so a character
+    // asked again, e.g. repeated in a passage, builds on its last review.
+    pub fn current(&self, chin: Chinese) -> Chinese {
+        self.items
+            .iter()
+            .rev()
+            .find(|x| x.code == chin.code && x.char == chin.char)
+            .cloned()
+            .unwrap_or(chin)
+    }
+}
+
+// Run a round of challenges.
+// Each answer reschedules the character, see schedule::review(),
+// and is recorded in the answers of the round for the history.
+// The contexts of a passage are shown with its characters, see passage.rs.
+pub fn run(
+    ui: &mut dyn Ui,
+    db: &CJDatabase,
+    items: Vec<Chinese>,
+    contexts: &[String],
+    mode: Mode,
+    settings: &Settings,
+    components: &Components,
+) -> Round {
+    let qcount = items.len();
+    let mut round = Round::default();
+
+    ui.begin(qcount);
+    let now = Instant::now();
+
+    let quiz = Quiz {
+        db,
+        mode,
+        settings,
+        components,
+    };
+    for (i, chin) in items.into_iter().enumerate() {
+        let context = contexts.get(i).map_or("", |x| x.as_str());
+        challenge(ui, &quiz, chin, (i + 1, qcount), context, &mut round);
+    }
+
+    round.time = now.elapsed();
+    ui.score(round.correct, qcount, round.time);
+    round
+}
+
+// What the questions of a round are asked with.
+pub struct Quiz<'a> {
+    pub db: &'a CJDatabase,
+    pub mode: Mode,
+    pub settings: &'a Settings,
+    pub components: &'a Components,
+}
+
+// Ask a question of a round, number from 1 of total questions, 0 for no
+// end.  The character is rescheduled and added to the items of the round
+// with the answer, the score of the round counts it if it is correct.
+// Return true if the answer scored.
+pub fn challenge(
+    ui: &mut dyn Ui,
+    quiz: &Quiz,
+    mut chin: Chinese,
+    (number, total): (usize, usize),
+    context: &str,
+    round: &mut Round,
+) -> bool {
+    let Quiz {
+        db,
+        mode,
+        settings,
+        components,
+    } = *quiz;
+    chin = round.current(chin);
+    // answer quality for the scheduler
+    let quality;
+    let is_phrase = phrase::is_phrase(&chin);
+    // the codes of the cangjie version being practised,
+    // those of the characters in a row for a phrase
+    let (full, others) = match is_phrase {
+        true => (phrase::codes(db, &chin), Vec::new()),
+        false => db.codes_in(&chin.char, settings.cangjie),
+    };
+    // no code to ask for, e.g. a character not in the database
+    if full.is_empty() {
+        return false;
+    }
+    let codes = match mode {
+        Mode::Quick => quick::quick_codes(&full),
+        _ => full.clone(),
+    };
+    let chars = db.chars_of(&chin.code);
+    let choices = match mode {
+        Mode::Choice => choice::options(db, &full[0], &db.codes_of(&chin.char)),
+        _ => Vec::new(),
+    };
+    let hints = match (is_phrase, full.contains(&chin.code)) {
+        (true, _) => phrase::hints(db, &chin, components),
+        (false, true) => hints(mode, &chin.char, &chin.code, components),
+        (false, false) => hints(mode, &chin.char, &full[0], components),
+    };
+    let question = Question {
+        number,
+        total,
+        mode,
+        chin: &chin,
+        codes: &codes,
+        choices: &choices,
+        hints: &hints,
+        context,
+    };
+
+    let start = Instant::now();
+    let mut reply = ui.ask(&question);
+    let millis = start.elapsed().as_millis() as u64;
+    reply.text = answer(mode, &choices, &reply.text);
+    let correct = is_correct(mode, &reply.text, &chin, &codes, &chars);
+    // the codes of the other cangjie versions,
+    // and the characters to pick from with a quick code
+    let mut notes = String::new();
+    if mode.letters() {
+        for x in others.iter() {
+            let other = match mode {
+                Mode::Quick => quick::quick_code(&x.code),
+                _ => x.code.clone(),
+            };
+            if let Version::Only(v) = x.version {
+                notes += &format!("\n  also {} in Cangjie {}", other, v);
+            }
+        }
+    }
+    if mode == Mode::Quick {
+        for x in codes.iter() {
+            notes += &format!("\n  {}", quick::ambiguity(db, x));
+        }
+    }
+    round.answers.push(Answer {
+        time: schedule::now(),
+        user: settings.user.clone().unwrap_or_default(),
+        mode: mode.key().to_string(),
+        code: chin.code.clone(),
+        char: chin.char.clone(),
+        correct,
+        millis,
+        typed: reply.text,
+    });
+
+    if correct {
+        // slow but correct answers are shaky, see schedule::is_shaky(),
+        // and each hint takes away some of the credit
+        quality = schedule::with_hints(schedule::quality(&chin, millis), reply.hints);
+        if reply.hints == 0 {
+            schedule::record_time(&mut chin, millis);
+        }
+        let msg = if quality < schedule::PASS {
+            format!("Correct, but the code was shown. Score: {}", round.correct)
+        } else {
+            round.correct += 1;
+            match reply.hints {
+                0 if quality < schedule::PERFECT => format!(
+                    "Correct, but slow ({:.1} seconds)! Score: {}",
+                    millis as f64 / 1000.0,
+                    round.correct
+                ),
+                0 => format!("Correct! Score: {}", round.correct),
+                1 => format!("Correct, with a hint! Score: {}", round.correct),
+                n => format!("Correct, with {} hints! Score: {}", n, round.correct),
+            }
+        };
+        ui.answered(true, round.correct, &(msg + &notes));
+    } else {
+        quality = schedule::FAIL;
+        let mut msg = match mode {
+            Mode::Reverse => format!(
+                "Wrong! {} should be \"{}\"!  Score: {}",
+                chin.code,
+                chars.join("\" or \""),
+                round.correct
+            ),
+            _ => format!(
+                "Wrong! {} should be \"{}\"!  Score: {}",
+                chin.char,
+                codes.join("\" or \""),
+                round.correct
+            ),
+        };
+        // show why the code is what it is
+        let pairs: Vec<(String, String)> = match mode {
+            Mode::Reverse => chars
+                .iter()
+                .map(|x| (x.clone(), chin.code.clone()))
+                .collect(),
+            _ if is_phrase => phrase::pairs(db, &chin),
+            _ => full
+                .iter()
+                .map(|x| (chin.char.clone(), x.clone()))
+                .collect(),
+        };
+        for (char, code) in pairs {
+            msg += &format!("\n  {}", radical::explain(&char, &code, components));
+        }
+        ui.answered(false, round.correct, &(msg + &notes));
+        if settings.practice {
+            let practice = Question {
+                number: 0,
+                ..question
+            };
+            loop {
+                let reply = ui.ask(&practice);
+                let typed = answer(mode, &choices, &reply.text);
+                // no more answers to come, e.g. end of file
+                if reply.ended || is_correct(mode, &typed, &chin, &codes, &chars) {
+                    break;
+                }
+            }
+        }
+    }
+
+    schedule::review(&mut chin, quality, schedule::now());
+    round.items.push(chin);
+    correct && quality >= schedule::PASS
+}
+
+fn show_banner() {
+    println!(
+        "{}",
+        "
+****************************
+*                          *
+*      Welcome to the      *
+*                          *
+*           C  J           *
+*                          *
+*        Challenges        *
+*                          *
+****************************
+"
+        .if_supports_color(Stream::Stdout, |x| x.bright_green())
+    );
+}
+
+fn show_score(score: usize, max_score: usize, time_taken: Duration) {
+    let msg = format!(
+        "
+***************************
+*                         *
+*   Score: {:4.0} %         *
+*    Time: {: >4} seconds   *
+*                         *
+***************************
+",
+        (score * 100 / max_score.max(1)) as u16,
+        time_taken.as_secs()
+    );
+    println!(
+        "{}",
+        msg.if_supports_color(Stream::Stdout, |x| x.bright_yellow())
+    );
+}
+
+// Enter the score on the high score board if it makes the top,
+// then show the board.  count: questions of a round, 0 for an arcade game
+pub fn high_score(ui: &mut dyn Ui, path: &str, mode: Mode, count: usize, round: &Round) {
+    let mut entry = Entry {
+        time: schedule::now(),
+        initials: String::new(),
+        mode: mode.key().to_string(),
+        count,
+        correct: round.correct,
+        seconds: round.time.as_secs(),
+    };
+    if entry.correct == 0 {
+        return;
+    }
+    let entries = highscore::load(path);
+    let Some(rank) = highscore::rank(&entries, &entry, highscore::TOP) else {
+        return;
+    };
+
+    entry.initials = ui.ask_initials(rank);
+    if let Err(e) = highscore::add(path, &entry) {
+        eprintln!("Unable to save the high score: {}", e);
+        return;
+    }
+    let entries = highscore::load(path);
+    ui.board(
+        &highscore::top(&entries, mode.key(), count, highscore::TOP),
+        &highscore::title(mode.key(), count),
+    );
+}
+
+// Ask user to continue, switch the quiz mode or quit.
+// Return the mode to play, or None to quit.
+fn ask_continue(mut mode: Mode) -> Option<Mode> {
+    let stdout = Term::buffered_stdout();
+
+    loop {
+        println!(
+            "Press C to continue, M to switch mode (now: {}), Q to quit.\n",
+            mode.name()
+        );
+        loop {
+            // no terminal to read keys from, e.g. input is redirected
+            let Ok(ch) = stdout.read_char() else {
+                return None;
+            };
+            match ch.to_ascii_uppercase() {
+                'C' => return Some(mode),
+                'M' => break,
+                'Q' => return None,
+                _ => continue,
+            }
+        }
+        mode = mode.next();
+    }
+}
+
+// Use the ratings of the scheme of a quiz mode: the quick ratings in
+// quick mode, the recognition ratings in choice mode, the full code
+// ratings otherwise.
+pub fn use_ratings(db: &mut CJDatabase, mode: Mode, user: Option<&str>) {
+    let progress = match (mode, user) {
+        (Mode::Quick, _) => Some(quick::progress_path(&db.path, user)),
+        (Mode::Choice, _) => Some(choice::progress_path(&db.path, user)),
+        (_, Some(name)) => Some(profile::progress_path(&db.path, name)),
+        (_, None) => None,
+    };
+    if db.progress == progress {
+        return;
+    }
+    match progress {
+        Some(path) => db.use_progress(&path),
+        None => db.drop_progress(),
+    }
+}
+
+// Play rounds of challenges until the user quits,
+// saving the database after each round.
+// In phrase mode, the round has phrases of the phrase file mixed in.
+pub fn play(
+    db: &mut CJDatabase,
+    phrases: &mut CJDatabase,
+    strategy: &dyn SelectionStrategy,
+    settings: &Settings,
+    ui: &mut dyn Ui,
+) {
+    let mut mode = settings.mode;
+    let components = Components::load(&radical::components_path(&db.path));
+    ui.banner();
+    loop {
+        mode = match ui.ask_continue(mode) {
+            Some(mode) => mode,
+            None => return,
+        };
+        use_ratings(db, mode, settings.user.as_deref());
+        let mut items = strategy.select(db, settings.count);
+        if mode == Mode::Phrase && !phrases.v.is_empty() {
+            let count = quota_of(settings.count, PHRASE_SHARE).max(1);
+            items.truncate(settings.count.saturating_sub(count));
+            items.extend(strategy.select(phrases, count));
+            items.shuffle(&mut thread_rng());
+        }
+        let round = run(ui, db, items, &[], mode, settings, &components);
+        high_score(
+            ui,
+            &sibling(&db.path, highscore::SCORES_FILE),
+            mode,
+            settings.count,
+            &round,
+        );
+        let (words, chars): (Vec<Chinese>, Vec<Chinese>) =
+            round.items.into_iter().partition(phrase::is_phrase);
+        db.update(chars);
+        if let Err(msg) = db.save() {
+            eprintln!("{}", msg);
+        }
+        if !words.is_empty() {
+            phrases.update(words);
+            if let Err(msg) = phrases.save() {
+                eprintln!("{}", msg);
+            }
+        }
+        if let Err(e) = history::append(&history::history_path(&db.path), &round.answers) {
+            eprintln!("Unable to save the history: {}", e);
+        }
+    }
+}
+
+#[test]
+fn test_show_banner() {
+    show_banner();
+}
+
+#[test]
+fn test_show_score() {
+    show_score(3, 10, Duration::from_secs(20));
+    show_score(0, 0, Duration::ZERO);
+}
+
+#[test]
+fn test_check_code() {
+    let codes = vec!["abjj".to_string()];
+    assert!(check_code("abjj", &codes));
+    assert!(!check_code("abj", &codes));
+    assert!(!check_code("abjjj", &codes));
+
+    // a character with an alternate code
+    let codes = vec!["ab".to_string(), "abc".to_string()];
+    assert!(check_code("ab", &codes));
+    assert!(check_code("abc", &codes));
+    assert!(!check_code("a", &codes));
+}
+
+#[test]
+fn test_hints() {
+    let components = Components::new();
+    assert_eq!(
+        hints(Mode::Code, "題", "aombc", &components),
+        vec![
+            "Hint: 5 keys  _ _ _ _ _",
+            "Hint: 日 _ _ _ _",
+            "Hint: 題 aombc = 日人 一月金 = 是 + 頁",
+        ]
+    );
+    assert_eq!(
+        hints(Mode::Quick, "題", "aombc", &components)[2],
+        "Hint: ac from 題 aombc = 日人 一月金 = 是 + 頁"
+    );
+    assert_eq!(
+        hints(Mode::Char, "日", "a", &components)[1..2],
+        ["Hint: 日"]
+    );
+    assert_eq!(hints(Mode::Choice, "題", "aombc", &components).len(), 2);
+    assert!(hints(Mode::Reverse, "題", "aombc", &components).is_empty());
+}
+
+#[test]
+fn test_prefix_len() {
+    let codes = vec!["aombc".to_string(), "aomb".to_string()];
+    assert_eq!(prefix_len("", &codes), 0);
+    assert_eq!(prefix_len("aom", &codes), 3);
+    assert_eq!(prefix_len("aombc", &codes), 5);
+    assert_eq!(prefix_len("aox", &codes), 2);
+    assert_eq!(prefix_len("x", &codes), 0);
+}
@@ -0,0 +1,396 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+use chrono::{Local, TimeZone};
+use console::{Key, Term};
+
+use super::database::write_lines;
+
+// High scores are kept in a board file in the data folder, one line each:
+// time,initials,mode,count,correct,seconds
+// e.g. 1700000000,DFG,code,10,9,42
+// Entries are ranked within the same mode and round size: the most
+// correct answers first, then the fastest, then the earliest.
+// Arcade games have no round size, their count is 0.
+
+pub const TOP: usize = 10; // entries shown on a board
+pub const SCORES_FILE: &str = "scores.csv"; // board of the rounds
+pub const ARCADE_FILE: &str = "arcade.csv"; // board of the arcade games
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Entry {
+    pub time: u64,        // seconds since the unix epoch
+    pub initials: String, // 3 letters, arcade style
+    pub mode: String,     // quiz mode
+    pub count: usize,     // number of questions, 0 for an arcade game
+    pub correct: usize,   // number of correct answers
+    pub seconds: u64,     // time taken
+}
+
+impl Entry {
+    pub fn to_line(&self) -> String {
+        format!(
+            "{},{},{},{},{},{}",
+            self.time, self.initials, self.mode, self.count, self.correct, self.seconds
+        )
+    }
+
+    // Parse a line of the board file, None if it is not readable.
+    pub fn parse(line: &str) -> Option<Entry> {
+        let parts: Vec<&str> = line.trim().split(',').collect();
+        if parts.len() < 6 {
+            return None;
+        }
+        Some(Entry {
+            time: parts[0].parse().ok()?,
+            initials: parts[1].to_string(),
+            mode: parts[2].to_string(),
+            count: parts[3].parse().ok()?,
+            correct: parts[4].parse().ok()?,
+            seconds: parts[5].parse().ok()?,
+        })
+    }
+
+    // e.g. "9/10", or "42" for an arcade game.
+    pub fn score(&self) -> String {
+        match self.count {
+            0 => self.correct.to_string(),
+            n => format!("{:>2}/{:<2}", self.correct, n),
+        }
+    }
+
+    // Return true if the entry ranks before the other one.
+    fn beats(&self, other: &Entry) -> bool {
+        (other.correct, self.seconds, self.time) < (self.correct, other.seconds, other.time)
+    }
+}
+
+// Load the entries of a board file, unreadable lines are skipped.
+pub fn load(path: &str) -> Vec<Entry> {
+    let Ok(file) = File::open(path) else {
+        return Vec::new();
+    };
+    let mut entries = Vec::new();
+    for line in BufReader::new(file).lines() {
+        match line {
+            Ok(x) => entries.extend(Entry::parse(&x)),
+            // not valid UTF-8, the next lines may be fine
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => continue,
+            Err(_) => break,
+        }
+    }
+    entries
+}
+
+// Append an entry to a board file.  Once the board of the entry has more
+// than TOP entries, the file is trimmed to the top entries of each board.
+pub fn add(path: &str, entry: &Entry) -> io::Result<()> {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", entry.to_line())?;
+    drop(file);
+
+    let entries = load(path);
+    let same = entries
+        .iter()
+        .filter(|x| x.mode == entry.mode && x.count == entry.count)
+        .count();
+    if same > TOP {
+        trim(path, TOP)?;
+    }
+    Ok(())
+}
+
+// Rewrite a board file with the top n entries of each mode and round
+// size, through a temp file.  Lines which are not readable are kept
+// first, with the bytes which are not valid UTF-8 replaced.
+fn trim(path: &str, n: usize) -> io::Result<()> {
+    let mut entries = Vec::new();
+    let mut others = Vec::new();
+    for line in BufReader::new(File::open(path)?).split(b'\n') {
+        let line = String::from_utf8_lossy(&line?).into_owned();
+        match Entry::parse(&line) {
+            Some(x) => entries.push(x),
+            None if line.trim().is_empty() => (),
+            None => others.push(line),
+        }
+    }
+    let mut boards: Vec<(&str, usize)> =
+        entries.iter().map(|x| (x.mode.as_str(), x.count)).collect();
+    boards.sort();
+    boards.dedup();
+
+    let kept = boards
+        .into_iter()
+        .flat_map(|(mode, count)| top(&entries, mode, count, n))
+        .map(|x| x.to_line());
+    write_lines(path, others.into_iter().chain(kept))
+}
+
+// Return the top entries of a mode and round size, best first.
+pub fn top(entries: &[Entry], mode: &str, count: usize, n: usize) -> Vec<Entry> {
+    let mut v: Vec<Entry> = entries
+        .iter()
+        .filter(|x| x.mode == mode && x.count == count)
+        .cloned()
+        .collect();
+    v.sort_by(|a, b| (b.correct, a.seconds, a.time).cmp(&(a.correct, b.seconds, b.time)));
+    v.truncate(n);
+    v
+}
+
+// Return the rank (from 1) the entry would get, if it makes the top n.
+pub fn rank(entries: &[Entry], entry: &Entry, n: usize) -> Option<usize> {
+    let better = top(entries, &entry.mode, entry.count, n)
+        .iter()
+        .filter(|x| !entry.beats(x))
+        .count();
+    if better < n {
+        Some(better + 1)
+    } else {
+        None
+    }
+}
+
+// Print a board of entries, best first.
+pub fn show(entries: &[Entry], title: &str) {
+    println!("\n  ===== {} =====", title);
+    println!("  RANK  NAME  SCORE   TIME  DATE");
+    for (i, x) in entries.iter().enumerate() {
+        println!(
+            "  {:>4}  {:4}  {:5}  {:>4}s  {}",
+            i + 1,
+            x.initials,
+            x.score(),
+            x.seconds,
+            date(x.time)
+        );
+    }
+}
+
+// Title of the board of a mode and round size, e.g. "CODE X 10" or
+// "ARCADE CODE".
+pub fn title(mode: &str, count: usize) -> String {
+    match count {
+        0 => format!("arcade {}", mode),
+        n => format!("{} x {}", mode, n),
+    }
+    .to_uppercase()
+}
+
+// Format a time as a local date.
+pub fn date(time: u64) -> String {
+    match Local.timestamp_opt(time as i64, 0) {
+        chrono::LocalResult::Single(x) => x.format("%Y-%m-%d").to_string(),
+        _ => "?".to_string(),
+    }
+}
+
+// 3 initials being entered arcade style:
+// type a letter or use up/down to change the current letter,
+// left to go back, right to go forward.
+pub struct Initials {
+    letters: [char; 3],
+    pos: usize, // letter being entered
+}
+
+impl Initials {
+    pub fn new() -> Initials {
+        Initials {
+            letters: ['A', 'A', 'A'],
+            pos: 0,
+        }
+    }
+
+    // Set the current letter and move to the next one.
+    pub fn letter(&mut self, ch: char) {
+        if ch.is_ascii_alphanumeric() {
+            self.letters[self.pos] = ch.to_ascii_uppercase();
+            self.right();
+        }
+    }
+
+    pub fn up(&mut self) {
+        self.letters[self.pos] = step(self.letters[self.pos], 1);
+    }
+
+    pub fn down(&mut self) {
+        self.letters[self.pos] = step(self.letters[self.pos], 25);
+    }
+
+    pub fn left(&mut self) {
+        self.pos = self.pos.saturating_sub(1);
+    }
+
+    pub fn right(&mut self) {
+        self.pos = (self.pos + 1).min(2);
+    }
+
+    // The letters with the current one in brackets, e.g. " D [F] A "
+    pub fn shown(&self) -> String {
+        self.letters
+            .iter()
+            .enumerate()
+            .map(|(i, x)| {
+                if i == self.pos {
+                    format!("[{}]", x)
+                } else {
+                    format!(" {} ", x)
+                }
+            })
+            .collect()
+    }
+
+    pub fn value(&self) -> String {
+        self.letters.iter().collect()
+    }
+}
+
+// Ask user to enter 3 initials, arcade style, see Initials.
+// Backspace also goes back, Enter to confirm.
+pub fn ask_initials() -> String {
+    println!("Enter your initials:");
+    let term = Term::stdout();
+
+    // no key by key input available, e.g. input is redirected
+    if !term.is_term() {
+        let mut line = String::new();
+        io::stdin().read_line(&mut line).unwrap_or(0);
+        return initials_of(&line);
+    }
+
+    let mut initials = Initials::new();
+    loop {
+        term.clear_line().unwrap_or(());
+        print!("{}", initials.shown());
+        term.flush().unwrap_or(());
+
+        match term.read_key() {
+            Ok(Key::Char(ch)) => initials.letter(ch),
+            Ok(Key::ArrowUp) => initials.up(),
+            Ok(Key::ArrowDown) => initials.down(),
+            Ok(Key::ArrowLeft) | Ok(Key::Backspace) => initials.left(),
+            Ok(Key::ArrowRight) => initials.right(),
+            Ok(Key::Enter) | Err(_) => break,
+            Ok(_) => (),
+        }
+    }
+    println!();
+    initials.value()
+}
+
+// Move a letter forward in A to Z, wrapping around.
+fn step(letter: char, n: u8) -> char {
+    let i = (letter as u8).saturating_sub(b'A') % 26;
+    (b'A' + (i + n) % 26) as char
+}
+
+// Return the first 3 letters or digits of a line, in upper case,
+// filled up with "-".
+fn initials_of(line: &str) -> String {
+    let mut s: String = line
+        .chars()
+        .filter(|x| x.is_ascii_alphanumeric())
+        .take(3)
+        .collect::<String>()
+        .to_ascii_uppercase();
+    while s.len() < 3 {
+        s.push('-');
+    }
+    s
+}
+
+#[cfg(test)]
+fn entry(time: u64, correct: usize, seconds: u64) -> Entry {
+    Entry {
+        time,
+        initials: "AAA".to_string(),
+        mode: "char".to_string(),
+        count: 10,
+        correct,
+        seconds,
+    }
+}
+
+#[test]
+fn test_entry_line() {
+    let x = entry(1700000000, 9, 42);
+    assert_eq!(x.to_line(), "1700000000,AAA,char,10,9,42");
+    assert_eq!(Entry::parse(&x.to_line()), Some(x.clone()));
+    assert_eq!(Entry::parse("1700000000,AAA,char"), None);
+    assert_eq!(x.score(), " 9/10");
+    assert_eq!(Entry { count: 0, ..x }.score(), "9");
+    assert_eq!(title("code", 10), "CODE X 10");
+    assert_eq!(title("code", 0), "ARCADE CODE");
+}
+
+#[test]
+fn test_top() {
+    let mut other = entry(5, 10, 1);
+    other.count = 20;
+    let entries = vec![
+        entry(1, 8, 30),
+        entry(2, 9, 50),
+        entry(3, 9, 40),
+        entry(4, 9, 40),
+        other,
+    ];
+    let times: Vec<u64> = top(&entries, "char", 10, 3)
+        .iter()
+        .map(|x| x.time)
+        .collect();
+    assert_eq!(times, vec![3, 4, 2]);
+    assert_eq!(top(&entries, "code", 10, 3), vec![]);
+}
+
+#[test]
+fn test_rank() {
+    let entries = vec![entry(1, 8, 30), entry(2, 9, 50)];
+    assert_eq!(rank(&entries, &entry(9, 10, 99), 3), Some(1));
+    assert_eq!(rank(&entries, &entry(9, 9, 50), 3), Some(2));
+    assert_eq!(rank(&entries, &entry(9, 0, 99), 3), Some(3));
+    assert_eq!(rank(&entries, &entry(9, 0, 99), 2), None);
+}
+
+#[test]
+fn test_add_trim() {
+    let path = "./tests/scores_temp.csv";
+    let _ = fs::remove_file(path);
+    let mut other = entry(0, 5, 10);
+    other.mode = "code".to_string();
+    add(path, &other).unwrap();
+    for i in 0..TOP as u64 {
+        add(path, &entry(i + 1, 5, 10 + i)).unwrap();
+    }
+    assert_eq!(load(path).len(), TOP + 1);
+
+    // one more than the board holds, the slowest goes
+    add(path, &entry(99, 5, 1)).unwrap();
+    let entries = load(path);
+    fs::remove_file(path).unwrap();
+    assert_eq!(entries.len(), TOP + 1);
+    assert_eq!(entries.iter().filter(|x| x.mode == "code").count(), 1);
+    assert_eq!(top(&entries, "char", 10, TOP)[0].time, 99);
+    assert!(!entries.iter().any(|x| x.time == TOP as u64));
+}
+
+#[test]
+// test the lines which are not readable survive a trim
+fn test_trim_keeps_unreadable() {
+    let path = "./tests/scores2_temp.csv";
+    let mut bytes = b"# my scores\n\xe9\xa1,char\n".to_vec();
+    for i in 0..TOP as u64 + 2 {
+        bytes.extend_from_slice(format!("{}\n", entry(i + 1, 5, 10 + i).to_line()).as_bytes());
+    }
+    fs::write(path, bytes).unwrap();
+
+    trim(path, TOP).unwrap();
+    let entries = load(path);
+    let text = String::from_utf8_lossy(&fs::read(path).unwrap()).into_owned();
+    fs::remove_file(path).unwrap();
+    assert!(text.starts_with("# my scores\n\u{fffd},char\n"));
+    assert_eq!(entries.len(), TOP);
+}
@@ -0,0 +1,198 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::Path;
+
+use super::database::{sibling, write_lines};
+
+// Every answer is appended to the history file in the data folder:
+// time,user,mode,code,char,correct,millis,typed
+// e.g. 1700000000,amy,code,aombc,題,0,5230,aomc
+// typed is the last field, it may contain commas.
+
+// When the history file grows beyond MAX_BYTES, the oldest lines are moved
+// to the archive file, keeping the newest KEEP_LINES in the history file.
+const MAX_BYTES: u64 = 4 * 1024 * 1024;
+const KEEP_LINES: usize = 20_000;
+
+// One answer to a question.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Answer {
+    pub time: u64,    // seconds since the unix epoch
+    pub user: String, // user profile, empty for shared ratings
+    pub mode: String, // quiz mode
+    pub code: String, // expected code
+    pub char: String, // expected character
+    pub correct: bool,
+    pub millis: u64,   // response time in milliseconds
+    pub typed: String, // what the user typed
+}
+
+impl Answer {
+    pub fn to_line(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{}",
+            self.time,
+            self.user,
+            self.mode,
+            self.code,
+            self.char,
+            self.correct as u8,
+            self.millis,
+            self.typed.replace(['\n', '\r'], " ")
+        )
+    }
+
+    // Parse a line of the history file, None if it is not readable.
+    pub fn parse(line: &str) -> Option<Answer> {
+        let parts: Vec<&str> = line.splitn(8, ',').collect();
+        if parts.len() < 8 {
+            return None;
+        }
+        Some(Answer {
+            time: parts[0].parse().ok()?,
+            user: parts[1].to_string(),
+            mode: parts[2].to_string(),
+            code: parts[3].to_string(),
+            char: parts[4].to_string(),
+            correct: parts[5] == "1",
+            millis: parts[6].parse().ok()?,
+            typed: parts[7].to_string(),
+        })
+    }
+}
+
+// Return the history file next to the data file.
+pub fn history_path(data: &str) -> String {
+    sibling(data, "history.csv")
+}
+
+// Append answers to the history file, rotating it if it is too big.
+pub fn append(path: &str, answers: &[Answer]) -> io::Result<()> {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    for x in answers {
+        writeln!(file, "{}", x.to_line())?;
+    }
+    drop(file);
+
+    if fs::metadata(path)?.len() > MAX_BYTES {
+        rotate(path, KEEP_LINES)?;
+    }
+    Ok(())
+}
+
+// Move all but the newest lines of the history file to the archive file.
+// The archive is appended first, then the history file is replaced through
+// a temp file, so a crash part way leaves duplicated lines, not lost ones.
+// A line which is not valid UTF-8 is moved with its bad bytes replaced,
+// so it does not stop the rotation.
+fn rotate(path: &str, keep: usize) -> io::Result<()> {
+    let mut lines = Vec::new();
+    for line in BufReader::new(File::open(path)?).split(b'\n') {
+        lines.push(String::from_utf8_lossy(&line?).into_owned());
+    }
+    if lines.len() <= keep {
+        return Ok(());
+    }
+    let (old, new) = lines.split_at(lines.len() - keep);
+
+    let archive = format!("{}.old", path);
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&archive)?;
+    for x in old {
+        writeln!(file, "{}", x)?;
+    }
+    file.sync_all()?;
+
+    write_lines(path, new.iter().cloned())
+}
+
+// Load the answers of the history file, of a user if given.
+// Lines which are not readable are skipped.
+pub fn load(path: &str, user: Option<&str>) -> Vec<Answer> {
+    let Ok(file) = File::open(path) else {
+        return Vec::new();
+    };
+    let mut answers = Vec::new();
+    for line in BufReader::new(file).lines() {
+        match line {
+            Ok(x) => answers.extend(Answer::parse(&x)),
+            // not valid UTF-8, the next lines may be fine
+            Err(e) if e.kind() == io::ErrorKind::InvalidData => continue,
+            Err(_) => break,
+        }
+    }
+    answers.retain(|x| user.is_none_or(|u| x.user == u));
+    answers
+}
+
+#[cfg(test)]
+fn answer(time: u64, correct: bool, typed: &str) -> Answer {
+    Answer {
+        time,
+        user: "amy".to_string(),
+        mode: "code".to_string(),
+        code: "aombc".to_string(),
+        char: "題".to_string(),
+        correct,
+        millis: 1500,
+        typed: typed.to_string(),
+    }
+}
+
+#[test]
+fn test_answer_line() {
+    let x = answer(1700000000, false, "a,b");
+    assert_eq!(x.to_line(), "1700000000,amy,code,aombc,題,0,1500,a,b");
+    assert_eq!(Answer::parse(&x.to_line()), Some(x));
+
+    assert_eq!(Answer::parse("1700000000,amy,code"), None);
+    assert_eq!(Answer::parse("x,amy,code,aombc,題,0,1500,"), None);
+}
+
+#[test]
+fn test_history_append_rotate() {
+    let path = "./tests/history_temp.csv";
+    let archive = format!("{}.old", path);
+    let _ = fs::remove_file(path);
+    let _ = fs::remove_file(&archive);
+
+    let answers: Vec<Answer> = (0..10).map(|i| answer(i, i % 2 == 0, "aombc")).collect();
+    append(path, &answers).unwrap();
+    assert_eq!(load(path, None), answers);
+    assert_eq!(load(path, Some("amy")).len(), 10);
+    assert!(load(path, Some("bob")).is_empty());
+
+    rotate(path, 4).unwrap();
+    assert_eq!(load(path, None), answers[6..].to_vec());
+    assert_eq!(load(&archive, None), answers[..6].to_vec());
+
+    fs::remove_file(path).unwrap();
+    fs::remove_file(archive).unwrap();
+}
+
+#[test]
+// test a line which is not valid UTF-8 does not stop the rotation
+fn test_history_rotate_not_utf8() {
+    let path = "./tests/history2_temp.csv";
+    let archive = format!("{}.old", path);
+    let _ = fs::remove_file(&archive);
+
+    let answers: Vec<Answer> = (0..4).map(|i| answer(i, true, "aombc")).collect();
+    let mut bytes = b"1,amy,code,aombc,\xe9\xa1,1,1500,aombc\n".to_vec();
+    for x in &answers {
+        bytes.extend_from_slice(format!("{}\n", x.to_line()).as_bytes());
+    }
+    fs::write(path, bytes).unwrap();
+
+    rotate(path, 2).unwrap();
+    assert_eq!(load(path, None), answers[2..].to_vec());
+    assert_eq!(load(&archive, None).len(), 3);
+
+    fs::remove_file(path).unwrap();
+    fs::remove_file(archive).unwrap();
+}
@@ -0,0 +1,266 @@
+use super::database::{CJDatabase, Chinese};
+use super::frequency::Frequency;
+use super::radical;
+use super::strategy::SelectionStrategy;
+
+// Lessons the way Cangjie is traditionally taught: first the 24 base
+// radicals in their four groups, then characters grouped by first key.
+// A lesson is complete once each of its characters has been answered
+// correctly, so the completion follows the ratings of the user.
+
+pub const LESSON_SIZE: usize = 20; // characters of a first key lesson
+
+// The radical groups and their keys, x (難) and z (重) are in none.
+pub static GROUPS: [(&str, &str); 4] = [
+    ("哲理", "abcdefg"), // philosophy
+    ("筆畫", "hijklmn"), // strokes
+    ("人體", "opqr"),    // body parts
+    ("字形", "stuvwy"),  // shapes
+];
+
+// Auxiliary shapes of the radicals, typed with the same key,
+// e.g. 氵 is typed with e (水).
+static AUXILIARY: [(char, &str); 19] = [
+    ('a', "曰"),
+    ('b', "⺼ 冂 爫"),
+    ('c', "八 丷"),
+    ('e', "氵 氺"),
+    ('f', "灬 ⺌"),
+    ('g', "士"),
+    ('h', "丿 ⺮"),
+    ('i', "丶 厶"),
+    ('j', "宀"),
+    ('k', "乂 𠂇"),
+    ('l', "丨 衤"),
+    ('m', "厂"),
+    ('p', "忄 匕"),
+    ('q', "扌"),
+    ('s', "匚"),
+    ('t', "艹"),
+    ('u', "凵"),
+    ('w', "囗"),
+    ('y', "亠 辶"),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Topic {
+    Radicals(usize), // the base radicals of a group, index of GROUPS
+    Key(char),       // characters whose code starts with a key
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Lesson {
+    pub number: usize, // from 1
+    pub topic: Topic,
+}
+
+// All the lessons in teaching order: the four radical groups, then the
+// first keys from a to y, without x.
+pub fn lessons() -> Vec<Lesson> {
+    let radicals = (0..GROUPS.len()).map(Topic::Radicals);
+    let keys = GROUPS
+        .iter()
+        .flat_map(|(_, keys)| keys.chars())
+        .map(Topic::Key);
+    radicals
+        .chain(keys)
+        .enumerate()
+        .map(|(i, topic)| Lesson {
+            number: i + 1,
+            topic,
+        })
+        .collect()
+}
+
+// Find a lesson by number.
+pub fn lesson(number: usize) -> Option<Lesson> {
+    lessons().into_iter().find(|x| x.number == number)
+}
+
+// Auxiliary shapes of a key, e.g. "氵 氺" for e.
+pub fn auxiliary(key: char) -> Option<&'static str> {
+    AUXILIARY.iter().find(|(k, _)| *k == key).map(|(_, x)| *x)
+}
+
+impl Lesson {
+    // e.g. "哲理 radicals A-G 日月金木水火土" or "first key A 日".
+    pub fn title(&self) -> String {
+        match self.topic {
+            Topic::Radicals(i) => {
+                let (name, keys) = GROUPS[i];
+                format!(
+                    "{} radicals {}-{} {}",
+                    name,
+                    keys.chars().next().unwrap_or(' ').to_ascii_uppercase(),
+                    keys.chars().last().unwrap_or(' ').to_ascii_uppercase(),
+                    radical::radicals(keys)
+                )
+            }
+            Topic::Key(key) => format!(
+                "first key {} {}",
+                key.to_ascii_uppercase(),
+                radical::radicals(&key.to_string())
+            ),
+        }
+    }
+
+    // The keys taught, with their radical and auxiliary shapes,
+    // e.g. "E 水  also 氵 氺".
+    pub fn keys(&self) -> Vec<String> {
+        let keys = match self.topic {
+            Topic::Radicals(i) => GROUPS[i].1.to_string(),
+            Topic::Key(key) => key.to_string(),
+        };
+        keys.chars()
+            .map(|k| {
+                let also = match auxiliary(k) {
+                    Some(x) => format!("  also {}", x),
+                    None => String::new(),
+                };
+                format!(
+                    "{} {}{}",
+                    k.to_ascii_uppercase(),
+                    radical::radicals(&k.to_string()),
+                    also
+                )
+            })
+            .collect()
+    }
+
+    // The characters of the lesson, from the database.
+    // A first key lesson has the most frequent characters of the key,
+    // or those of the shortest codes without frequency ranks.
+    pub fn items<'a>(&self, db: &'a CJDatabase, ranks: &Frequency) -> Vec<&'a Chinese> {
+        match self.topic {
+            Topic::Radicals(i) => GROUPS[i]
+                .1
+                .chars()
+                .flat_map(|k| {
+                    let radical = radical::radicals(&k.to_string());
+                    db.find_code(&k.to_string())
+                        .into_iter()
+                        .filter(move |x| x.char == radical)
+                })
+                .collect(),
+            Topic::Key(key) => {
+                let mut found: Vec<&Chinese> = db
+                    .find_prefix(&key.to_string())
+                    .into_iter()
+                    .filter(|x| x.code.len() > 1)
+                    .collect();
+                found.sort_by_key(|x| {
+                    let rank = ranks.rank(&x.char).unwrap_or(usize::MAX);
+                    (rank, x.code.len(), x.code.clone())
+                });
+                let mut items: Vec<&Chinese> = Vec::new();
+                for x in found {
+                    if !items.iter().any(|y| y.char == x.char) {
+                        items.push(x);
+                    }
+                    if items.len() >= LESSON_SIZE {
+                        break;
+                    }
+                }
+                items
+            }
+        }
+    }
+}
+
+// Return the characters answered correctly and all the characters of
+// a lesson.
+pub fn progress(items: &[&Chinese]) -> (usize, usize) {
+    (items.iter().filter(|x| x.rating > 0).count(), items.len())
+}
+
+// Play the characters of a lesson, those not learnt yet first, then the
+// weakest ones.  A lesson smaller than a round is repeated.
+pub struct Lessons {
+    pub lesson: Lesson,
+    pub ranks: Frequency,
+}
+
+impl SelectionStrategy for Lessons {
+    fn name(&self) -> &'static str {
+        "lesson"
+    }
+
+    fn description(&self) -> &'static str {
+        "the characters of a lesson, see cj lessons"
+    }
+
+    fn select(&self, db: &CJDatabase, item_count: usize) -> Vec<Chinese> {
+        let mut items: Vec<&Chinese> = self.lesson.items(db, &self.ranks);
+        items.sort_by_key(|x| (x.rating > 0, x.rating));
+        items
+            .iter()
+            .cycle()
+            .take(item_count)
+            .map(|x| (*x).clone())
+            .collect()
+    }
+}
+
+#[test]
+fn test_lessons() {
+    let all = lessons();
+    assert_eq!(all.len(), 28);
+    assert_eq!(all[0].title(), "哲理 radicals A-G 日月金木水火土");
+    assert_eq!(all[3].title(), "字形 radicals S-Y 尸廿山女田卜");
+    assert_eq!(all[4].title(), "first key A 日");
+    assert_eq!(all[27].topic, Topic::Key('y'));
+    assert_eq!(lesson(5), Some(all[4]));
+    assert_eq!(lesson(29), None);
+    assert_eq!(all[0].keys()[4], "E 水  also 氵 氺");
+    assert_eq!(all[2].keys()[3], "R 口");
+}
+
+#[test]
+fn test_lesson_items() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj05.csv");
+    let ranks = Frequency::default();
+
+    let items = lesson(1).unwrap().items(&db, &ranks);
+    let chars: Vec<&str> = items.iter().map(|x| x.char.as_str()).collect();
+    assert_eq!(chars.concat(), "日月金木水火土");
+    let items = lesson(5).unwrap().items(&db, &ranks);
+    assert_eq!(items.len(), LESSON_SIZE);
+    assert!(items
+        .iter()
+        .all(|x| x.code.starts_with('a') && x.code.len() > 1));
+    assert!(items.windows(2).all(|x| x[0].code.len() <= x[1].code.len()));
+
+    let (passed, total) = progress(&items);
+    assert_eq!(total, items.len());
+    assert_eq!(passed, items.iter().filter(|x| x.rating > 0).count());
+
+    let strategy = Lessons {
+        lesson: lesson(5).unwrap(),
+        ranks,
+    };
+    let selected = strategy.select(&db, total + 3);
+    assert_eq!(selected.len(), total + 3);
+    assert!(selected.iter().all(|x| x.code.starts_with('a')));
+}
+
+#[test]
+// test a character asked twice in a lesson round counts twice
+fn test_lesson_cycle_reviews() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj05.csv");
+    let strategy = Lessons {
+        lesson: lesson(1).unwrap(),
+        ranks: Frequency::default(),
+    };
+    let mut round = super::game::Round::default();
+    for chin in strategy.select(&db, 14) {
+        let mut chin = round.current(chin);
+        super::schedule::review(&mut chin, super::schedule::PERFECT, 1700000000);
+        round.items.push(chin);
+    }
+    db.update(round.items);
+    for x in lesson(1).unwrap().items(&db, &Frequency::default()) {
+        assert_eq!(x.rating, 2);
+    }
+}
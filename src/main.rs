@@ -1,150 +1,309 @@
-use std::time::{Duration, Instant};
+use clap::Parser;
 
-use console::Term;
-
-use database::{CJDatabase, Chinese};
-use owo_colors::OwoColorize;
+use cli::{ArcadeArgs, Cli, Command, PassageArgs, PlayArgs};
+use config::Config;
+use database::{CJDatabase, DATA_FILE, VERSIONS};
+use frequency::Frequency;
+use game::{LineUi, Mode, Settings, Ui};
+use passage::Passage;
+use strategy::{Strategies, StrategyConfig};
+use tui::{Frontend, Tui};
 
+mod arcade;
+mod choice;
+mod cli;
+mod commands;
+mod config;
+mod confusion;
 mod data;
 mod database;
+mod frequency;
+mod game;
+mod highscore;
+mod history;
+mod lesson;
+mod passage;
+mod phrase;
+mod profile;
+mod quick;
+mod radical;
+mod schedule;
+mod strategy;
+mod table;
+mod tui;
 
 const QUESTION_COUNT: usize = 10; // at least 10 questions
 
-// Ask user to enter a chinese char.
-// Return true if correct, false otherwise.
-fn ask(prompt: &str, chinchar: &String) -> bool {
-    println!("{}[{}]?", prompt, chinchar);
-    let mut line = String::new();
-    std::io::stdin().read_line(&mut line).unwrap();
-    //println!("{}={}", chinchar, line.trim());
-    chinchar == line.trim()
-}
-
-// On correct code => set rating = 1 if current rating = -1, else increase rating by 1
-// On incorrect code => set rating = -1
-fn run(items: Vec<Chinese>) -> Vec<Chinese> {
-    let mut score = 0; // for this challenge
-    let mut count = 0;
-    let qcount = items.len();
-    let mut results: Vec<Chinese> = Vec::new();
-    let mut rating; // character statistics
-    let mut prefix;
-
-    println!("\n======== C H A L L E N G E   B E G I N S ========");
-    let now = Instant::now();
-
-    for mut chin in items.into_iter() {
-        count = count + 1;
-        if count > qcount {
-            break;
+// Play the game, command line options take precedence over the config file.
+fn play(
+    db: &mut CJDatabase,
+    args: PlayArgs,
+    config: Config,
+    user: Option<String>,
+    color: bool,
+) -> Result<(), String> {
+    if db.v.is_empty() {
+        return Err(no_characters(db));
+    }
+    let ranks = Frequency::load(&frequency::frequency_path(&db.path));
+    let top = args.top.or(config.top);
+    let mut name = args
+        .strategy
+        .or(config.strategy)
+        .unwrap_or("smart".to_string());
+    // --lesson N plays the lesson strategy, clap refuses it with --strategy
+    let mut lesson = None;
+    if let Some(number) = args.lesson {
+        let found = match lesson::lesson(number) {
+            Some(x) => x,
+            None => return Err(format!("no such lesson: {}, see cj lessons", number)),
+        };
+        if found.items(db, &ranks).is_empty() {
+            return Err(format!(
+                "lesson {} has no characters in the data file",
+                number
+            ));
         }
-
-        println!("");
-        prefix = format!("#{}/{} ", count, qcount);
-
-        if ask(&prefix, &chin.char) {
-            rating = 1;
-            score = score + 1;
-            println!("Correct! Score: {}", score);
-        } else {
-            rating = -1;
-            println!(
-                "Wrong! {} should be \"{}\"!  Score: {}",
-                chin.char, chin.code, score
-            );
-            while !ask("Practice:", &chin.char) {}
+        lesson = Some(found);
+        name = "lesson".to_string();
+    }
+    if name == "curriculum" && ranks.is_empty() {
+        match top {
+            Some(n) => {
+                return Err(format!(
+                    "--top {} needs character frequencies, see cj frequency --help",
+                    n
+                ))
+            }
+            None => eprintln!("No character frequencies yet, see cj frequency --help"),
         }
-
-        if rating == (-1) {
-            chin.rating = rating;
-        } else {
-            if chin.rating == (-1) {
-                chin.rating = 1;
-            } else {
-                chin.rating += 1;
+    }
+    let strategies = Strategies::new(StrategyConfig {
+        weights: config.weights,
+        ranks,
+        top,
+        lesson,
+        history: history::history_path(&db.path),
+        user: user.clone(),
+    });
+    let strategy = match strategies.get(&name) {
+        Some(s) => s,
+        None => {
+            let mut msg = format!("unknown strategy: {}\nAvailable strategies:", name);
+            for s in strategies.iter() {
+                msg += &format!("\n  {:10} {}", s.name(), s.description());
             }
+            return Err(msg);
         }
-        results.push(chin);
+    };
+    let cangjie = cangjie(args.cangjie.or(config.cangjie))?;
+    let settings = Settings {
+        count: args.count.or(config.count).unwrap_or(QUESTION_COUNT).max(1),
+        mode: match args.lesson {
+            Some(_) => args.mode.unwrap_or(Mode::Code),
+            None => args.mode.or(config.mode).unwrap_or(Mode::Char),
+        },
+        practice: args.practice.or(config.practice).unwrap_or(true),
+        user,
+        cangjie,
+    };
+
+    // phrases for the phrase mode, with ratings of their own
+    let mut phrases = phrase::open(&phrase::phrases_path(&db.path), db);
+    if let (Some(name), false) = (&settings.user, phrases.v.is_empty()) {
+        phrases.use_progress(&phrase::progress_path(&db.path, name));
     }
 
-    let elapsed_time = now.elapsed();
-    show_score(score, qcount, elapsed_time);
+    let mut ui = frontend(args.ui.or(config.ui), color)?;
+    game::play(db, &mut phrases, strategy, &settings, ui.as_mut());
+    Ok(())
+}
 
-    results
+// Return the Cangjie version practised, None for any, or an error if
+// it is not one of VERSIONS.
+fn cangjie(version: Option<u8>) -> Result<Option<u8>, String> {
+    if let Some(v) = version.filter(|v| !VERSIONS.contains(v)) {
+        return Err(format!("unknown Cangjie version: {} (3 or 5)", v));
+    }
+    Ok(version)
 }
 
-fn show_banner() {
-    println!(
-        "{}",
-        "
-****************************
-*                          *
-*      Welcome to the      *
-*                          *
-*           C  J           *
-*                          *
-*        Challenges        *
-*                          *
-****************************
-"
-        .bright_green()
-    );
+// Type a passage, of a text file or a built-in one.
+fn passage(
+    db: &mut CJDatabase,
+    args: PassageArgs,
+    config: Config,
+    user: Option<String>,
+    color: bool,
+) -> Result<(), String> {
+    if args.list {
+        for (i, (title, _)) in data::PASSAGES.iter().enumerate() {
+            println!("{:2}. {}", i + 1, title);
+        }
+        return Ok(());
+    }
+    let passage = match (&args.file, args.number) {
+        (Some(file), _) => Passage::load(file)?,
+        (None, Some(n)) => match Passage::builtin(n) {
+            Some(x) => x,
+            None => return Err(format!("no such passage: {}, see cj passage --list", n)),
+        },
+        (None, None) => Passage::random(),
+    };
+    let cangjie = cangjie(args.cangjie.or(config.cangjie))?;
+    let settings = Settings {
+        count: 0, // the whole passage
+        mode: args.mode.unwrap_or(Mode::Code),
+        practice: config.practice.unwrap_or(true),
+        user,
+        cangjie,
+    };
+
+    let mut ui = frontend(args.ui.or(config.ui), color)?;
+    passage::play(db, &passage, &settings, ui.as_mut())
 }
 
-fn show_score(score: i16, max_score: usize, time_taken: Duration) {
-    let msg = format!(
-        "
-***************************
-*                         *
-*   Score: {:4.0} %         *
-*    Time: {: >4} seconds   *
-*                         *
-***************************
-",
-        (score as usize * 100 / max_score) as u16,
-        time_taken.as_secs()
-    );
-    println!("{}", msg.bright_yellow());
+// Play arcade games against the clock.
+fn arcade(
+    db: &mut CJDatabase,
+    args: ArcadeArgs,
+    config: Config,
+    user: Option<String>,
+    color: bool,
+) -> Result<(), String> {
+    if db.v.is_empty() {
+        return Err(no_characters(db));
+    }
+    let cangjie = cangjie(args.cangjie.or(config.cangjie))?;
+    let settings = Settings {
+        count: 0, // until the time runs out
+        mode: args.mode.unwrap_or(Mode::Code),
+        practice: false, // no time for it
+        user,
+        cangjie,
+    };
+
+    let mut ui = frontend(args.ui.or(config.ui), color)?;
+    arcade::play(db, &settings, ui.as_mut());
+    Ok(())
 }
 
-fn ask_continue(msg: &str) -> bool {
-    println!("{}\n", msg);
-    let stdout = Term::buffered_stdout();
+// Error of an empty data file, nothing to play.
+fn no_characters(db: &CJDatabase) -> String {
+    format!(
+        "no characters in {}, add some with cj add or cj import",
+        db.path
+    )
+}
 
-    loop {
-        if let Ok(ch) = stdout.read_char() {
-            match ch.to_ascii_uppercase() {
-                'C' => return true,
-                'Q' => return false,
-                _ => continue,
-            }
-        }
-    }
+// Full screen if possible, line by line for dumb terminals.
+fn frontend(choice: Option<Frontend>, color: bool) -> Result<Box<dyn Ui>, String> {
+    Ok(match choice.unwrap_or(Frontend::Auto) {
+        Frontend::Line => Box::new(LineUi),
+        Frontend::Tui => match Tui::new(color) {
+            Ok(tui) => Box::new(tui),
+            Err(e) => return Err(format!("unable to start the full screen UI: {}", e)),
+        },
+        Frontend::Auto if tui::available() => match Tui::new(color) {
+            Ok(tui) => Box::new(tui),
+            Err(_) => Box::new(LineUi),
+        },
+        Frontend::Auto => Box::new(LineUi),
+    })
 }
 
 fn main() {
-    println!("Initiating CJ Challenges...");
-
-    let mut db = CJDatabase { v: Vec::new() };
-    db.load();
-    db.sort();
-    db.dedup();
-    db.save();
-
-    show_banner();
-    loop {
-        println!("");
-        if !ask_continue("Press C to continue, Q to quit.") {
-            return;
+    let cli = Cli::parse();
+    let config = match Config::load(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(msg) => {
+            eprintln!("Error: {}", msg);
+            std::process::exit(1);
         }
-        let items = db.get_items_smart(QUESTION_COUNT);
-        let results = run(items);
-        db.update(results);
-        db.save();
+    };
+    let color = cli.color.or(config.color).unwrap_or(true);
+    if !color {
+        owo_colors::set_override(false);
     }
-}
+    let command = cli.command.unwrap_or(Command::Play(PlayArgs::default()));
+
+    let playing = matches!(
+        command,
+        Command::Play(_) | Command::Passage(_) | Command::Arcade(_)
+    );
+    if playing {
+        println!("Initiating CJ Challenges...");
+    }
+    let data = cli
+        .data
+        .or(config.data.clone())
+        .unwrap_or(DATA_FILE.to_string());
 
-#[test]
-fn test_show_banner() {
-    show_banner();
+    // pick a profile at start up if there are any
+    let user = match cli.user.or(config.user.clone()) {
+        Some(name) if !profile::is_valid_name(&name) => {
+            eprintln!("Error: invalid user name: {}", name);
+            std::process::exit(1);
+        }
+        Some(name) => Some(name),
+        None if playing && !profile::list(&data).is_empty() => profile::pick(&data),
+        None => None,
+    };
+
+    let mut db = CJDatabase::open(&data);
+    // the ratings of a game could not be saved, see CJDatabase::check()
+    if let (true, Err(msg)) = (playing, db.check()) {
+        eprintln!("Error: {}", msg);
+        std::process::exit(1);
+    }
+    if let Some(name) = &user {
+        db.use_progress(&profile::progress_path(&data, name));
+    }
+
+    let result = match command {
+        Command::Play(args) => play(&mut db, args, config, user, color),
+        Command::Stats { quick, choice } => {
+            if quick {
+                db.use_progress(&quick::progress_path(&data, user.as_deref()));
+            }
+            if choice {
+                db.use_progress(&choice::progress_path(&data, user.as_deref()));
+            }
+            commands::stats(&db, user.as_deref());
+            Ok(())
+        }
+        Command::Reset { code, yes } => commands::reset(&mut db, code, yes),
+        Command::Add { code, char } => commands::add(&mut db, &code, &char),
+        Command::Lookup { query } => commands::lookup(&db, &query),
+        Command::Import {
+            file,
+            format,
+            cangjie,
+        } => commands::import(&mut db, &file, format, cangjie),
+        Command::Export { file } => commands::export(&mut db, &file),
+        Command::Scores {
+            mode,
+            count,
+            top,
+            arcade,
+        } => commands::scores(&db, mode, count, top, arcade),
+        Command::Profiles => {
+            commands::profiles(&db);
+            Ok(())
+        }
+        Command::Passage(args) => passage(&mut db, args, config, user, color),
+        Command::Arcade(args) => arcade(&mut db, args, config, user, color),
+        Command::Lessons { number } => commands::lessons(&db, number),
+        Command::Frequency { file } => commands::frequency(&db, &file),
+        Command::Confusions { top } => {
+            commands::confusions(&db, user.as_deref(), top);
+            Ok(())
+        }
+        Command::Doctor { fix } => commands::doctor(&mut db, fix),
+    };
+
+    if let Err(msg) = result {
+        eprintln!("Error: {}", msg);
+        std::process::exit(1);
+    }
 }
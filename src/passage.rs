@@ -0,0 +1,228 @@
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+use rand::{thread_rng, Rng};
+
+use super::data::PASSAGES;
+use super::database::{CJDatabase, Chinese};
+use super::game::{self, Mode, Round, Settings, Ui};
+use super::history;
+use super::radical::{self, Components};
+
+const CONTEXT: usize = 8; // characters shown before and after the one to type
+
+// A text to type character by character, a text file or a built-in one.
+// Punctuation, spaces and ASCII are passed over.
+pub struct Passage {
+    pub title: String,
+    pub text: String,
+}
+
+impl Passage {
+    // A built-in passage, numbered from 1.
+    pub fn builtin(number: usize) -> Option<Passage> {
+        let (title, text) = PASSAGES.get(number.checked_sub(1)?)?;
+        Some(Passage {
+            title: title.to_string(),
+            text: text.to_string(),
+        })
+    }
+
+    // Any built-in passage.
+    pub fn random() -> Passage {
+        let (title, text) = PASSAGES[thread_rng().gen_range(0..PASSAGES.len())];
+        Passage {
+            title: title.to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    // Load a UTF-8 text file, titled with its file name.
+    pub fn load(path: &str) -> Result<Passage, String> {
+        let text =
+            fs::read_to_string(path).map_err(|e| format!("unable to read {}: {}", path, e))?;
+        let title = Path::new(path)
+            .file_name()
+            .map_or(path.to_string(), |x| x.to_string_lossy().to_string());
+        Ok(Passage {
+            title,
+            text: text.trim_start_matches('\u{feff}').to_string(),
+        })
+    }
+}
+
+// The characters of a passage to type, with the text around each one,
+// and the characters not in the database with how often they occur.
+#[derive(Debug, Default)]
+pub struct Walk {
+    pub items: Vec<Chinese>,
+    pub contexts: Vec<String>, // e.g. "床前明月光，【疑】是地上霜。 舉頭"
+    pub missing: Vec<(char, usize)>,
+}
+
+// Return true if a character of a passage is to be typed.
+fn is_typed(c: char) -> bool {
+    c.is_alphabetic() && !c.is_ascii()
+}
+
+// Walk a passage character by character, looking up the record of each
+// one in the database, of the cangjie version practised if it has one.
+pub fn walk(db: &CJDatabase, text: &str, cangjie: Option<u8>) -> Walk {
+    let all: Vec<char> = text
+        .chars()
+        .map(|c| if c.is_whitespace() { ' ' } else { c })
+        .collect();
+    let mut walk = Walk::default();
+    for (i, &c) in all.iter().enumerate().filter(|(_, &c)| is_typed(c)) {
+        let found = db.find_char(&c.to_string());
+        let chin = found
+            .iter()
+            .find(|x| x.version.accepts(cangjie))
+            .or(found.first());
+        let Some(chin) = chin else {
+            match walk.missing.iter_mut().find(|(x, _)| *x == c) {
+                Some((_, n)) => *n += 1,
+                None => walk.missing.push((c, 1)),
+            }
+            continue;
+        };
+        let before: String = all[i.saturating_sub(CONTEXT)..i].iter().collect();
+        let after: String = all[i + 1..(i + 1 + CONTEXT).min(all.len())]
+            .iter()
+            .collect();
+        walk.items.push((*chin).clone());
+        walk.contexts.push(format!(
+            "{}【{}】{}",
+            before.trim_start(),
+            c,
+            after.trim_end()
+        ));
+    }
+    walk
+}
+
+// Characters per minute, counting the correct ones.
+pub fn per_minute(correct: usize, time: Duration) -> f64 {
+    match time.as_secs_f64() {
+        secs if secs > 0.0 => correct as f64 * 60.0 / secs,
+        _ => 0.0,
+    }
+}
+
+// Typing speed and accuracy of a passage, and the characters skipped.
+fn report(round: &Round, total: usize, missing: &[(char, usize)]) -> String {
+    let mut text = format!(
+        "Characters per minute: {:.1}\nAccuracy: {} % ({}/{})",
+        per_minute(round.correct, round.time),
+        round.correct * 100 / total.max(1),
+        round.correct,
+        total
+    );
+    if !missing.is_empty() {
+        let chars: Vec<String> = missing
+            .iter()
+            .map(|(c, n)| match n {
+                1 => c.to_string(),
+                n => format!("{} x{}", c, n),
+            })
+            .collect();
+        text += &format!("\nNot in the data file, skipped: {}", chars.join(" "));
+    }
+    text
+}
+
+// Type a passage, as many times as the user likes.
+// Each character typed is rated like the characters of a round.
+pub fn play(
+    db: &mut CJDatabase,
+    passage: &Passage,
+    settings: &Settings,
+    ui: &mut dyn Ui,
+) -> Result<(), String> {
+    if walk(db, &passage.text, settings.cangjie).items.is_empty() {
+        return Err(format!(
+            "no characters of {} are in the data file",
+            passage.title
+        ));
+    }
+    let components = Components::load(&radical::components_path(&db.path));
+    let mut mode = settings.mode;
+    ui.banner();
+    loop {
+        mode = match ui.ask_continue(mode) {
+            Some(mode) => mode,
+            None => return Ok(()),
+        };
+        game::use_ratings(db, mode, settings.user.as_deref());
+        // a passage has characters to type, the reverse mode has none
+        let typed = match mode {
+            Mode::Reverse => Mode::Code,
+            mode => mode,
+        };
+        let walk = walk(db, &passage.text, settings.cangjie);
+        let total = walk.items.len();
+        let round = game::run(
+            ui,
+            db,
+            walk.items,
+            &walk.contexts,
+            typed,
+            settings,
+            &components,
+        );
+        ui.report(&report(&round, total, &walk.missing));
+        if let Err(e) = history::append(&history::history_path(&db.path), &round.answers) {
+            eprintln!("Unable to save the history: {}", e);
+        }
+        db.update(round.items);
+        if let Err(msg) = db.save() {
+            eprintln!("{}", msg);
+        }
+    }
+}
+
+#[test]
+fn test_walk() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj06.csv");
+    let walk = walk(&db, "早 時, 晏？\n犇 早犇", None);
+    let chars: Vec<&str> = walk.items.iter().map(|x| x.char.as_str()).collect();
+    assert_eq!(chars, vec!["早", "時", "晏", "早"]);
+    assert_eq!(walk.contexts[1], "早 【時】, 晏？ 犇 早");
+    assert_eq!(walk.contexts[3], "時, 晏？ 犇 【早】犇");
+    assert_eq!(walk.missing, vec![('犇', 2)]);
+}
+
+#[test]
+// test a character repeated in a passage counts each time
+fn test_walk_repeated() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj06.csv");
+    let mut round = Round::default();
+    for chin in walk(&db, "早早早", None).items {
+        let mut chin = round.current(chin);
+        super::schedule::review(&mut chin, super::schedule::PERFECT, 1700000000);
+        round.items.push(chin);
+    }
+    db.update(round.items);
+    let chin = &db.find_char("早")[0];
+    assert_eq!(chin.rating, 3);
+}
+
+#[test]
+fn test_passages() {
+    assert!(Passage::builtin(0).is_none());
+    assert!(Passage::builtin(PASSAGES.len() + 1).is_none());
+    assert!(Passage::builtin(1).unwrap().text.starts_with("床前明月光"));
+    assert!(Passage::load("./tests/no_such_passage.txt").is_err());
+
+    // every character of the built-in passages is in the full data
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj05.csv");
+    for (_, text) in PASSAGES.iter() {
+        assert!(walk(&db, text, None).missing.is_empty());
+    }
+    assert_eq!(per_minute(30, Duration::from_secs(60)), 30.0);
+    assert_eq!(per_minute(1, Duration::ZERO), 0.0);
+}
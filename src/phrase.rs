@@ -0,0 +1,198 @@
+use std::path::Path;
+
+use super::database::{sibling, CJDatabase, Chinese};
+use super::profile;
+use super::radical::{self, Components};
+
+// Phrases (詞語) of two or more characters, e.g. 明天, are kept apart from
+// the characters in a phrase file next to the data file, in the data file
+// format with ratings of their own:
+//   ab mk,明天,0       the codes of the characters, separated by spaces
+//   ,時間,0            the codes left out, filled in from the data file
+//   amk,明天,0         a phrase code of an input method
+// A phrase is answered by typing the codes of its characters in a row,
+// e.g. abmk, or its phrase code if it has one.
+
+pub const PHRASES_FILE: &str = "phrases.csv";
+pub const PHRASE_SHARE: f32 = 0.3; // of a round in phrase mode
+const MAX_SPELLINGS: usize = 16; // codes accepted for a phrase
+
+// Return true if a record is a phrase rather than a character.
+pub fn is_phrase(chin: &Chinese) -> bool {
+    chin.char.chars().count() > 1
+}
+
+// Return the phrase file next to the data file.
+pub fn phrases_path(data: &str) -> String {
+    sibling(data, PHRASES_FILE)
+}
+
+// Return the progress file of the phrase ratings of a user profile,
+// <data folder>/profiles/<name>.phrases.csv
+pub fn progress_path(data: &str, name: &str) -> String {
+    profile::mode_progress_path(data, Some(name), "phrases")
+}
+
+// Load the phrase file, if there is one, filling in the codes left out
+// from the characters of the database.  Phrases with a character not in
+// the database are not asked, they stay in the phrase file as they are.
+pub fn open(path: &str, db: &CJDatabase) -> CJDatabase {
+    let mut phrases = CJDatabase::new(path);
+    phrases.phrases = true;
+    if !Path::new(path).exists() {
+        return phrases;
+    }
+    phrases.load();
+    for x in phrases.v.iter_mut().filter(|x| x.code.is_empty()) {
+        match spell(db, &x.char) {
+            Some(code) => x.code = code,
+            None => eprintln!("{}: not asked, not in the data file: {}", path, x.char),
+        }
+    }
+    let (unknown, known): (Vec<Chinese>, Vec<Chinese>) =
+        phrases.v.drain(..).partition(|x| x.code.is_empty());
+    phrases.v = known;
+    phrases
+        .kept_lines
+        .extend(unknown.iter().map(|x| x.to_line()));
+    phrases.sort();
+    phrases.dedup();
+    phrases
+}
+
+// The codes of the characters of a phrase separated by spaces, the first
+// code of each, e.g. "ab mk" for 明天.  None if a character is unknown.
+pub fn spell(db: &CJDatabase, phrase: &str) -> Option<String> {
+    let codes: Option<Vec<String>> = phrase
+        .chars()
+        .map(|c| db.codes_of(&c.to_string()).into_iter().next())
+        .collect();
+    codes.map(|x| x.join(" "))
+}
+
+// The codes accepted for a phrase: its own codes in a row, then those of
+// its characters in a row, with any code of each character.
+pub fn codes(db: &CJDatabase, chin: &Chinese) -> Vec<String> {
+    let mut codes = vec![chin.code.replace(' ', "")];
+    let mut spellings = vec![String::new()];
+    for c in chin.char.chars() {
+        let alternatives = db.codes_of(&c.to_string());
+        spellings = spellings
+            .iter()
+            .flat_map(|x| alternatives.iter().map(move |y| format!("{}{}", x, y)))
+            .take(MAX_SPELLINGS)
+            .collect();
+    }
+    for x in spellings.into_iter().filter(|x| !x.is_empty()) {
+        if !codes.contains(&x) {
+            codes.push(x);
+        }
+    }
+    codes
+}
+
+// Each character of a phrase with its code, to explain a wrong answer.
+// The codes of the phrase are used when they are those of its characters.
+pub fn pairs(db: &CJDatabase, chin: &Chinese) -> Vec<(String, String)> {
+    let own: Vec<&str> = chin.code.split(' ').collect();
+    chin.char
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let c = c.to_string();
+            let code = match own.len() == chin.char.chars().count() {
+                true => own[i].to_string(),
+                false => db.codes_of(&c).into_iter().next().unwrap_or_default(),
+            };
+            (c, code)
+        })
+        .collect()
+}
+
+// Hints to the codes of a phrase: the number of keys of each character,
+// the first radical of each, then the whole codes.
+pub fn hints(db: &CJDatabase, chin: &Chinese, components: &Components) -> Vec<String> {
+    let pairs = pairs(db, chin);
+    let blanks = |n: usize| vec!["_"; n].join(" ");
+    let keys: usize = pairs.iter().map(|(_, code)| code.len()).sum();
+    let spaced: Vec<String> = pairs.iter().map(|(_, code)| blanks(code.len())).collect();
+    let first: Vec<String> = pairs
+        .iter()
+        .map(|(_, code)| {
+            let radical = radical::radicals(code.get(..1).unwrap_or(""));
+            format!("{} {}", radical, blanks(code.len().saturating_sub(1)))
+                .trim()
+                .to_string()
+        })
+        .collect();
+    let whole: Vec<String> = pairs
+        .iter()
+        .map(|(c, code)| radical::explain(c, code, components))
+        .collect();
+    vec![
+        format!("Hint: {} keys  {}", keys, spaced.join("   ")),
+        format!("Hint: {}", first.join("   ")),
+        format!("Hint: {}", whole.join("   ")),
+    ]
+}
+
+#[test]
+fn test_phrase_codes() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj05.csv");
+
+    assert_eq!(spell(&db, "明天").as_deref(), Some("ab mk"));
+    assert_eq!(spell(&db, "明犇"), None);
+
+    let chin = Chinese::new("ab mk", "明天");
+    assert_eq!(codes(&db, &chin), vec!["abmk"]);
+    let chin = Chinese::new("amk", "明天");
+    assert_eq!(codes(&db, &chin), vec!["amk", "abmk"]);
+    assert_eq!(
+        pairs(&db, &chin),
+        vec![
+            ("明".to_string(), "ab".to_string()),
+            ("天".to_string(), "mk".to_string())
+        ]
+    );
+
+    let hints = hints(&db, &chin, &Components::default());
+    assert_eq!(hints[0], "Hint: 4 keys  _ _   _ _");
+    assert_eq!(hints[1], "Hint: 日 _   一 _");
+    assert_eq!(hints[2], "Hint: 明 ab = 日月   天 mk = 一大");
+}
+
+#[test]
+fn test_phrases_open() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj05.csv");
+    let phrases = open("./tests/cj12.csv", &db);
+    let records: Vec<(&str, &str, i16)> = phrases
+        .v
+        .iter()
+        .map(|x| (x.code.as_str(), x.char.as_str(), x.rating))
+        .collect();
+    assert_eq!(records, vec![("ab mk", "明天", 0), ("agdi ana", "時間", 2)]);
+    assert!(phrases.v.iter().all(is_phrase));
+    assert_eq!(phrases.kept_lines, vec![",明犇,0"]);
+    assert!(open("./tests/no_such_phrases.csv", &db).v.is_empty());
+    // the single character would be lost
+    assert!(phrases.check().is_err());
+}
+
+#[test]
+// test a save keeps the comments and the phrases not in the data file
+fn test_phrases_save() {
+    let path = "./tests/cj12_temp.csv";
+    std::fs::write(path, "# my phrases\n,明犇,0\nab mk,明天,0\n").unwrap();
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj05.csv");
+
+    let mut phrases = open(path, &db);
+    assert_eq!(phrases.v.len(), 1);
+    phrases.v[0].rating = 1;
+    phrases.save().unwrap();
+    let text = std::fs::read_to_string(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(text, "# my phrases\n,明犇,0\nab mk,明天,1\n");
+}
@@ -0,0 +1,128 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use super::database::sibling;
+
+// User profiles share the data file, but keep their ratings in a
+// progress file each: <data folder>/profiles/<name>.csv
+// Without a profile, the ratings are kept in the data file.
+
+// Return the folder of the progress files.
+pub fn profiles_dir(data: &str) -> PathBuf {
+    PathBuf::from(sibling(data, "profiles"))
+}
+
+// Return the progress file of a profile.
+pub fn progress_path(data: &str, name: &str) -> String {
+    profiles_dir(data)
+        .join(format!("{}.csv", name))
+        .to_string_lossy()
+        .to_string()
+}
+
+// Return the progress file of a mode whose ratings are kept apart, e.g.
+// quick: <data folder>/quick.csv, or <data folder>/profiles/<name>.quick.csv
+// for a user profile.
+pub fn mode_progress_path(data: &str, user: Option<&str>, mode: &str) -> String {
+    match user {
+        Some(name) => profiles_dir(data)
+            .join(format!("{}.{}.csv", name, mode))
+            .to_string_lossy()
+            .to_string(),
+        None => sibling(data, &format!("{}.csv", mode)),
+    }
+}
+
+// Return the names of the existing profiles, sorted.
+pub fn list(data: &str) -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(profiles_dir(data))
+        .map(|dir| {
+            dir.filter_map(|x| x.ok())
+                .map(|x| x.path())
+                .filter(|x| x.extension().is_some_and(|ext| ext == "csv"))
+                .filter_map(|x| x.file_stem().map(|s| s.to_string_lossy().to_string()))
+                .filter(|x| is_valid_name(x))
+                .collect()
+        })
+        .unwrap_or_default();
+    names.sort();
+    names
+}
+
+// A profile name is 1 to 20 letters, digits, - or _.
+pub fn is_valid_name(name: &str) -> bool {
+    (1..=20).contains(&name.chars().count())
+        && name
+            .chars()
+            .all(|x| x.is_alphanumeric() || x == '-' || x == '_')
+}
+
+// Ask user to pick a profile, or to enter a new name.
+// Return None for no profile, i.e. the ratings in the data file.
+pub fn pick(data: &str) -> Option<String> {
+    let names = list(data);
+    println!("\nWho is playing?");
+    println!("  0. (shared ratings)");
+    for (i, name) in names.iter().enumerate() {
+        println!("  {}. {}", i + 1, name);
+    }
+
+    loop {
+        println!("Enter a number or a new name:");
+        let mut line = String::new();
+        if io::stdin().read_line(&mut line).unwrap_or(0) == 0 {
+            return None;
+        }
+        let line = line.trim();
+        if line.is_empty() || line == "0" {
+            return None;
+        }
+        if let Ok(i) = line.parse::<usize>() {
+            if (1..=names.len()).contains(&i) {
+                return Some(names[i - 1].clone());
+            }
+            continue;
+        }
+        if is_valid_name(line) {
+            return Some(line.to_string());
+        }
+        println!("A name is 1 to 20 letters, digits, - or _.");
+    }
+}
+
+#[test]
+fn test_is_valid_name() {
+    assert!(is_valid_name("david"));
+    assert!(is_valid_name("小明"));
+    assert!(is_valid_name("kid-2"));
+    assert!(!is_valid_name(""));
+    assert!(!is_valid_name("../x"));
+    assert!(!is_valid_name("a b"));
+    assert!(!is_valid_name("abcdefghijklmnopqrstu"));
+}
+
+#[test]
+fn test_progress_path() {
+    assert_eq!(
+        PathBuf::from(&progress_path("./data/cj.csv", "amy")),
+        PathBuf::from("./data/profiles/amy.csv")
+    );
+    assert_eq!(
+        PathBuf::from(&progress_path("cj.csv", "amy")),
+        PathBuf::from("profiles/amy.csv")
+    );
+}
+
+#[test]
+fn test_mode_progress_path() {
+    let path = mode_progress_path("/tmp/cj/cj.csv", None, "quick");
+    assert_eq!(PathBuf::from(&path), PathBuf::from("/tmp/cj/quick.csv"));
+    let path = mode_progress_path("/tmp/cj/cj.csv", Some("amy"), "choice");
+    assert_eq!(
+        PathBuf::from(&path),
+        PathBuf::from("/tmp/cj/profiles/amy.choice.csv")
+    );
+    // not taken for a profile of its own
+    assert!(!is_valid_name("amy.quick"));
+}
@@ -0,0 +1,81 @@
+use super::database::CJDatabase;
+use super::profile;
+
+// 速成 (Quick, or Simplified Cangjie) uses only the first and the last
+// letter of a cangjie code, so many more characters share a code.
+// The quick ratings are kept apart from the full code ratings, in a
+// progress file of their own, see progress_path().
+
+const SHOWN: usize = 10; // characters shown of those sharing a quick code
+
+// Return the quick code of a cangjie code, e.g. "aombc" => "ac".
+pub fn quick_code(code: &str) -> String {
+    match (code.chars().next(), code.chars().last()) {
+        (Some(first), Some(last)) if code.len() > 1 => format!("{}{}", first, last),
+        _ => code.to_string(),
+    }
+}
+
+// Return the distinct quick codes of cangjie codes.
+pub fn quick_codes(codes: &[String]) -> Vec<String> {
+    let mut quick: Vec<String> = Vec::new();
+    for x in codes.iter().map(|x| quick_code(x)) {
+        if !quick.contains(&x) {
+            quick.push(x);
+        }
+    }
+    quick
+}
+
+// Return the characters sharing a quick code, in the order of the database.
+pub fn sharing(db: &CJDatabase, quick: &str) -> Vec<String> {
+    let mut chars: Vec<String> = Vec::new();
+    for x in db.v.iter().filter(|x| quick_code(&x.code) == quick) {
+        if !chars.contains(&x.char) {
+            chars.push(x.char.clone());
+        }
+    }
+    chars
+}
+
+// Show the characters sharing a quick code, e.g. "ac: 題 暸 (2 characters)".
+pub fn ambiguity(db: &CJDatabase, quick: &str) -> String {
+    let chars = sharing(db, quick);
+    let more = if chars.len() > SHOWN { " ..." } else { "" };
+    let plural = if chars.len() == 1 { "" } else { "s" };
+    format!(
+        "{}: {}{} ({} character{})",
+        quick,
+        chars[..chars.len().min(SHOWN)].join(" "),
+        more,
+        chars.len(),
+        plural
+    )
+}
+
+// Return the progress file of the quick ratings:
+// <data folder>/quick.csv, or <data folder>/profiles/<name>.quick.csv
+// for a user profile.
+pub fn progress_path(data: &str, user: Option<&str>) -> String {
+    profile::mode_progress_path(data, user, "quick")
+}
+
+#[test]
+fn test_quick_code() {
+    assert_eq!(quick_code("aombc"), "ac");
+    assert_eq!(quick_code("ab"), "ab");
+    assert_eq!(quick_code("a"), "a");
+    assert_eq!(quick_code(""), "");
+
+    let codes = vec!["abjj".to_string(), "aj".to_string(), "ab".to_string()];
+    assert_eq!(quick_codes(&codes), vec!["aj", "ab"]);
+}
+
+#[test]
+fn test_sharing() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj06.csv");
+    assert_eq!(sharing(&db, "ac"), vec!["顥", "顯", "曠", "題"]);
+    assert_eq!(ambiguity(&db, "ac"), "ac: 顥 顯 曠 題 (4 characters)");
+    assert_eq!(ambiguity(&db, "zz"), "zz:  (0 characters)");
+}
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::fs;
+
+use super::data::COMPONENTS;
+use super::database::sibling;
+
+pub const COMPONENTS_FILE: &str = "components.csv"; // more components, optional
+
+// Cangjie radicals, one for each key.
+// a to y are the 24 radicals (x is used for difficult characters),
+// z is the collision key.
+pub static RADICALS: [(char, &str); 26] = [
+    ('a', "日"),
+    ('b', "月"),
+    ('c', "金"),
+    ('d', "木"),
+    ('e', "水"),
+    ('f', "火"),
+    ('g', "土"),
+    ('h', "竹"),
+    ('i', "戈"),
+    ('j', "十"),
+    ('k', "大"),
+    ('l', "中"),
+    ('m', "一"),
+    ('n', "弓"),
+    ('o', "人"),
+    ('p', "心"),
+    ('q', "手"),
+    ('r', "口"),
+    ('s', "尸"),
+    ('t', "廿"),
+    ('u', "山"),
+    ('v', "女"),
+    ('w', "田"),
+    ('x', "難"),
+    ('y', "卜"),
+    ('z', "重"),
+];
+
+// Return the radical of a key, case insensitive.
+pub fn radical(key: char) -> Option<&'static str> {
+    let key = key.to_ascii_lowercase();
+    RADICALS.iter().find(|(k, _)| *k == key).map(|(_, r)| *r)
+}
+
+// Spell out a cangjie code in radicals, e.g. "abjj" => "日月十十".
+// Unknown keys are shown as "?".
+pub fn radicals(code: &str) -> String {
+    code.chars().map(|k| radical(k).unwrap_or("?")).collect()
+}
+
+// The components of characters and the part of the code each one encodes,
+// e.g. 題 = 是 (ao) + 頁 (mbc).
+// Each line: character,component:part,component:part,...
+// e.g. 題,是:ao,頁:mbc
+// The parts make up the code of the character.
+#[derive(Default)]
+pub struct Components {
+    map: HashMap<(String, String), Vec<(String, String)>>, // (char, code) => [(component, part)]
+}
+
+impl Components {
+    // The built-in components.
+    pub fn new() -> Components {
+        let mut components = Components::default();
+        for line in COMPONENTS.lines() {
+            components.add(line);
+        }
+        components
+    }
+
+    // The built-in components and those of a components file, if any.
+    pub fn load(path: &str) -> Components {
+        let mut components = Components::new();
+        if let Ok(text) = fs::read_to_string(path) {
+            for (i, line) in text.lines().enumerate() {
+                if !components.add(line) {
+                    eprintln!("{}:{}: skipped, bad components: {}", path, i + 1, line);
+                }
+            }
+        }
+        components
+    }
+
+    // Add the components of a line, return false if it cannot be read.
+    // Blank lines and # comments are ignored.
+    fn add(&mut self, line: &str) -> bool {
+        let line = line.trim_start_matches('\u{feff}').trim();
+        if line.is_empty() || line.starts_with('#') {
+            return true;
+        }
+        let mut fields = line.split(',').map(|x| x.trim());
+        let char = fields.next().unwrap_or_default();
+        let mut parts = Vec::new();
+        for field in fields {
+            match field.split_once(':') {
+                Some((component, part)) if !component.is_empty() && !part.is_empty() => {
+                    parts.push((component.to_string(), part.to_lowercase()))
+                }
+                _ => return false,
+            }
+        }
+        if char.chars().count() != 1 || parts.is_empty() {
+            return false;
+        }
+        let code: String = parts.iter().map(|(_, part)| part.as_str()).collect();
+        self.map.insert((char.to_string(), code), parts);
+        true
+    }
+
+    // Return the components of a character for one of its codes.
+    pub fn get(&self, char: &str, code: &str) -> Option<&[(String, String)]> {
+        self.map
+            .get(&(char.to_string(), code.to_string()))
+            .map(|x| x.as_slice())
+    }
+}
+
+// Return the components file next to the data file.
+pub fn components_path(data: &str) -> String {
+    sibling(data, COMPONENTS_FILE)
+}
+
+// Explain the code of a character with the radical of each key,
+// grouped by component where the components are known, e.g.
+// "題 aombc = 日人 一月金 = 是 + 頁", otherwise "暉 abjj = 日月十十".
+pub fn explain(char: &str, code: &str, components: &Components) -> String {
+    match components.get(char, code) {
+        Some(parts) => {
+            let keys: Vec<String> = parts.iter().map(|(_, part)| radicals(part)).collect();
+            let names: Vec<&str> = parts.iter().map(|(name, _)| name.as_str()).collect();
+            format!(
+                "{} {} = {} = {}",
+                char,
+                code,
+                keys.join(" "),
+                names.join(" + ")
+            )
+        }
+        None => format!("{} {} = {}", char, code, radicals(code)),
+    }
+}
+
+#[test]
+fn test_radical() {
+    assert_eq!(radical('a'), Some("日"));
+    assert_eq!(radical('Y'), Some("卜"));
+    assert_eq!(radical('1'), None);
+}
+
+#[test]
+fn test_radicals() {
+    assert_eq!(radicals("abjj"), "日月十十");
+    assert_eq!(radicals("aombc"), "日人一月金");
+    assert_eq!(radicals(""), "");
+    assert_eq!(radicals("a1"), "日?");
+}
+
+#[test]
+fn test_components() {
+    let mut components = Components::new();
+    let parts = components.get("題", "aombc").unwrap();
+    assert_eq!(parts[0], ("是".to_string(), "ao".to_string()));
+    assert_eq!(parts[1], ("頁".to_string(), "mbc".to_string()));
+    assert!(components.get("題", "aomb").is_none());
+
+    assert!(components.add("# more"));
+    assert!(components.add("暉, 日:A , 軍:bjj"));
+    assert!(!components.add("暉,日"));
+    assert!(!components.add("暉"));
+    assert!(!components.add("暉暈,日:a,軍:bjj"));
+    assert!(components.get("暉", "abjj").is_some());
+}
+
+#[test]
+fn test_explain() {
+    let components = Components::new();
+    assert_eq!(
+        explain("題", "aombc", &components),
+        "題 aombc = 日人 一月金 = 是 + 頁"
+    );
+    assert_eq!(explain("暉", "abjj", &components), "暉 abjj = 日月十十");
+}
+
+#[test]
+fn test_components_pristine() {
+    // every built-in component list makes up a code of its character
+    let records: Vec<&str> = super::data::PRISTINE.lines().collect();
+    let components = Components::new();
+    for (char, code) in components.map.keys() {
+        assert!(
+            records.contains(&format!("{},{},0", code, char).as_str()),
+            "{} {}",
+            char,
+            code
+        );
+    }
+}
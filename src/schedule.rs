@@ -0,0 +1,201 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::database::Chinese;
+
+pub const DEFAULT_EASE: f32 = 2.5;
+const MIN_EASE: f32 = 1.3;
+const DAY: u64 = 24 * 60 * 60; // seconds
+
+// Answer quality, SM-2 style: 0 (blackout) to 5 (perfect).
+// Anything below PASS counts as a wrong answer.
+pub const PERFECT: u8 = 5;
+pub const PASS: u8 = 3;
+pub const FAIL: u8 = 1;
+
+// Response times, a correct answer slower than BASE_MILLIS plus
+// KEY_MILLIS for each key of the code is shaky.
+pub const TIMES_KEPT: usize = 5; // recent response times kept per character
+const BASE_MILLIS: u32 = 2000; // reading the question
+const KEY_MILLIS: u32 = 600; // typing a key of the code
+pub const SHAKY: u32 = 100; // slowness of a shaky character, see slowness()
+
+// Current time in seconds since the unix epoch.
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|x| x.as_secs())
+        .unwrap_or(0)
+}
+
+// Reschedule a character after an answer, using the SM-2 algorithm.
+// On correct answer => set rating = 1 if current rating = -1, else increase rating by 1,
+//                      the next review is 1 day, 6 days, then interval * ease later
+// On incorrect answer => set rating = -1, the next review is 1 day later
+// The ease factor goes down with poor answers and up with perfect ones.
+pub fn review(ch: &mut Chinese, quality: u8, now: u64) {
+    let q = quality.min(PERFECT) as f32;
+
+    if quality >= PASS {
+        ch.rating = if ch.rating < 0 {
+            1
+        } else {
+            ch.rating.saturating_add(1)
+        };
+        ch.interval = match ch.rating {
+            1 => 1,
+            2 => 6,
+            _ => ((ch.interval.max(1) as f32) * ch.ease).round() as u32,
+        };
+    } else {
+        ch.rating = -1;
+        ch.interval = 1;
+    }
+
+    ch.ease = (ch.ease + 0.1 - (5.0 - q) * (0.08 + (5.0 - q) * 0.02)).max(MIN_EASE);
+    ch.due = now + ch.interval as u64 * DAY;
+}
+
+// Time in ms within which a correct answer is fluent.
+pub fn slow_millis(ch: &Chinese) -> u32 {
+    BASE_MILLIS + KEY_MILLIS * ch.code.len() as u32
+}
+
+// Answer quality of a correct answer: PERFECT when fluent, PASS when slow.
+pub fn quality(ch: &Chinese, millis: u64) -> u8 {
+    if millis > slow_millis(ch) as u64 {
+        PASS
+    } else {
+        PERFECT
+    }
+}
+
+// Answer quality of a correct answer after some hints, one step lower
+// for each hint: with all 3 hints it falls below PASS.
+pub fn with_hints(quality: u8, hints: usize) -> u8 {
+    quality.min(PERFECT.saturating_sub(hints.min(PERFECT as usize) as u8))
+}
+
+// Keep the response time of a correct answer, dropping the oldest.
+pub fn record_time(ch: &mut Chinese, millis: u64) {
+    ch.times.push(millis.min(u32::MAX as u64) as u32);
+    if ch.times.len() > TIMES_KEPT {
+        ch.times.remove(0);
+    }
+}
+
+// Median response time in percent of the fluent time, 0 if unknown.
+pub fn slowness(ch: &Chinese) -> u32 {
+    ch.median().map_or(0, |x| x * 100 / slow_millis(ch))
+}
+
+// Return true if the character is answered correctly, but slowly.
+pub fn is_shaky(ch: &Chinese) -> bool {
+    ch.rating > 0 && slowness(ch) > SHAKY
+}
+
+// Return true if the character has been scheduled and its review is due.
+pub fn is_due(ch: &Chinese, now: u64) -> bool {
+    ch.due > 0 && ch.due <= now
+}
+
+#[test]
+fn test_review_correct() {
+    let mut ch = Chinese {
+        char: "題".to_string(),
+        code: "aombc".to_string(),
+        ..Default::default()
+    };
+    let t = 1_000_000;
+
+    review(&mut ch, PERFECT, t);
+    assert_eq!(ch.rating, 1);
+    assert_eq!(ch.interval, 1);
+    assert_eq!(ch.due, t + DAY);
+
+    review(&mut ch, PERFECT, t);
+    assert_eq!(ch.rating, 2);
+    assert_eq!(ch.interval, 6);
+
+    let ease = ch.ease;
+    review(&mut ch, PERFECT, t);
+    assert_eq!(ch.rating, 3);
+    assert_eq!(ch.interval, (6.0 * ease).round() as u32);
+    assert!(ch.ease > DEFAULT_EASE);
+    // a hand edited rating at the top stays there
+    ch.rating = i16::MAX;
+    review(&mut ch, PERFECT, t);
+    assert_eq!(ch.rating, i16::MAX);
+}
+
+#[test]
+fn test_review_wrong() {
+    let mut ch = Chinese {
+        char: "題".to_string(),
+        code: "aombc".to_string(),
+        rating: 5,
+        interval: 30,
+        ..Default::default()
+    };
+    let t = 1_000_000;
+
+    review(&mut ch, FAIL, t);
+    assert_eq!(ch.rating, -1);
+    assert_eq!(ch.interval, 1);
+    assert!(ch.ease < DEFAULT_EASE);
+
+    review(&mut ch, PERFECT, t);
+    assert_eq!(ch.rating, 1);
+
+    // ease never drops below the minimum
+    for _ in 0..20 {
+        review(&mut ch, 0, t);
+    }
+    assert_eq!(ch.ease, MIN_EASE);
+}
+
+#[test]
+fn test_is_due() {
+    let mut ch = Chinese::default();
+    assert!(!is_due(&ch, 100));
+    ch.due = 100;
+    assert!(is_due(&ch, 100));
+    assert!(!is_due(&ch, 99));
+}
+
+#[test]
+fn test_with_hints() {
+    assert_eq!(with_hints(PERFECT, 0), PERFECT);
+    assert_eq!(with_hints(PERFECT, 1), 4);
+    assert_eq!(with_hints(PERFECT, 2), PASS);
+    assert!(with_hints(PERFECT, 3) < PASS);
+    assert_eq!(with_hints(PASS, 1), PASS);
+    assert_eq!(with_hints(PERFECT, 9), 0);
+}
+
+#[test]
+fn test_response_times() {
+    let mut ch = Chinese {
+        char: "題".to_string(),
+        code: "aombc".to_string(),
+        rating: 2,
+        ..Default::default()
+    };
+    assert_eq!(slow_millis(&ch), 5000);
+    assert_eq!(quality(&ch, 4000), PERFECT);
+    assert_eq!(quality(&ch, 6000), PASS);
+    assert!(!is_shaky(&ch));
+
+    for t in [9000, 8000, 7000, 6000, 2000, 3000] {
+        record_time(&mut ch, t);
+    }
+    assert_eq!(ch.times, vec![8000, 7000, 6000, 2000, 3000]);
+    assert_eq!(ch.median(), Some(6000));
+    assert_eq!(slowness(&ch), 120);
+    assert!(is_shaky(&ch));
+
+    // fluent again
+    record_time(&mut ch, 2000);
+    record_time(&mut ch, 2000);
+    assert_eq!(ch.median(), Some(2000));
+    assert!(!is_shaky(&ch));
+}
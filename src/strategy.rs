@@ -0,0 +1,380 @@
+use rand::seq::IteratorRandom;
+use rand::thread_rng;
+
+use super::confusion::Confusions;
+use super::database::{quota_of, CJDatabase, Chinese, SmartWeights};
+use super::frequency::Frequency;
+use super::lesson::{Lesson, Lessons};
+use super::schedule;
+
+// A way to pick the characters for a round of challenges.
+// To add a strategy, implement this trait and register it in
+// Strategies::new(), it is then available by name on the command line.
+pub trait SelectionStrategy {
+    // Name used to select the strategy, e.g. "smart".
+    fn name(&self) -> &'static str;
+
+    // One line description for the user.
+    fn description(&self) -> &'static str;
+
+    // Return item_count characters from the database.
+    fn select(&self, db: &CJDatabase, item_count: usize) -> Vec<Chinese>;
+}
+
+// Any characters, duplicates allowed.
+pub struct Random;
+
+impl SelectionStrategy for Random {
+    fn name(&self) -> &'static str {
+        "random"
+    }
+
+    fn description(&self) -> &'static str {
+        "any characters at random"
+    }
+
+    fn select(&self, db: &CJDatabase, item_count: usize) -> Vec<Chinese> {
+        db.get_items_random(item_count as i32)
+    }
+}
+
+// A third each of difficult, new and easy characters.
+pub struct Score;
+
+impl SelectionStrategy for Score {
+    fn name(&self) -> &'static str {
+        "score"
+    }
+
+    fn description(&self) -> &'static str {
+        "a third each of difficult, new and easy characters"
+    }
+
+    fn select(&self, db: &CJDatabase, item_count: usize) -> Vec<Chinese> {
+        db.get_items_score(item_count)
+    }
+}
+
+// Due reviews first, then mostly new characters.
+#[derive(Default)]
+pub struct Smart {
+    pub weights: SmartWeights,
+}
+
+impl SelectionStrategy for Smart {
+    fn name(&self) -> &'static str {
+        "smart"
+    }
+
+    fn description(&self) -> &'static str {
+        "due reviews first, then mostly new characters"
+    }
+
+    fn select(&self, db: &CJDatabase, item_count: usize) -> Vec<Chinese> {
+        db.get_items_smart(item_count, &self.weights)
+    }
+}
+
+// The tested characters with the lowest rating and ease first,
+// topped up with new characters.
+pub struct WeakestFirst;
+
+impl SelectionStrategy for WeakestFirst {
+    fn name(&self) -> &'static str {
+        "weakest"
+    }
+
+    fn description(&self) -> &'static str {
+        "tested characters with the lowest rating first"
+    }
+
+    fn select(&self, db: &CJDatabase, item_count: usize) -> Vec<Chinese> {
+        let mut tested: Vec<&Chinese> = db.v.iter().filter(|x| x.due > 0).collect();
+        tested.sort_by(|a, b| a.rating.cmp(&b.rating).then(a.ease.total_cmp(&b.ease)));
+
+        let mut items: Vec<Chinese> = tested.into_iter().take(item_count).cloned().collect();
+        top_up(&db.v.iter().collect::<Vec<_>>(), &mut items, item_count);
+        items
+    }
+}
+
+// All characters due for review, most overdue first,
+// topped up with new characters.
+pub struct DueFirst;
+
+impl SelectionStrategy for DueFirst {
+    fn name(&self) -> &'static str {
+        "due"
+    }
+
+    fn description(&self) -> &'static str {
+        "characters due for review only, most overdue first"
+    }
+
+    fn select(&self, db: &CJDatabase, item_count: usize) -> Vec<Chinese> {
+        let now = schedule::now();
+        let mut due: Vec<&Chinese> = db.v.iter().filter(|x| schedule::is_due(x, now)).collect();
+        due.sort_by_key(|x| x.due);
+
+        let mut items: Vec<Chinese> = due.into_iter().take(item_count).cloned().collect();
+        top_up(&db.v.iter().collect::<Vec<_>>(), &mut items, item_count);
+        items
+    }
+}
+
+// Due reviews and wrong answers first, then new characters in frequency
+// order, the most common ones first.  With a top N, only the N most
+// frequent characters are picked, the round is shorter if there are
+// not enough of them.
+#[derive(Default)]
+pub struct Curriculum {
+    pub weights: SmartWeights, // quotas of due and wrong answers
+    pub ranks: Frequency,
+    pub top: Option<usize>, // only the top N most frequent characters
+}
+
+impl SelectionStrategy for Curriculum {
+    fn name(&self) -> &'static str {
+        "curriculum"
+    }
+
+    fn description(&self) -> &'static str {
+        "new characters in frequency order, see cj frequency"
+    }
+
+    fn select(&self, db: &CJDatabase, item_count: usize) -> Vec<Chinese> {
+        let now = schedule::now();
+        let pool: Vec<&Chinese> =
+            db.v.iter()
+                .filter(|x| self.ranks.in_top(&x.char, self.top))
+                .collect();
+
+        let mut due: Vec<&Chinese> = pool
+            .iter()
+            .filter(|x| schedule::is_due(x, now))
+            .copied()
+            .collect();
+        due.sort_by_key(|x| x.due);
+        let mut items: Vec<Chinese> = due
+            .into_iter()
+            .take(quota_of(item_count, self.weights.due))
+            .cloned()
+            .collect();
+
+        let quota = quota_of(item_count, self.weights.difficult);
+        let difficult: Vec<&Chinese> = pool
+            .iter()
+            .filter(|x| x.rating < 0 && !items.contains(x))
+            .copied()
+            .take(quota)
+            .collect();
+        items.extend(difficult.into_iter().cloned());
+
+        // unranked characters last, in the order of the database
+        let mut new: Vec<&Chinese> = pool.iter().filter(|x| !x.is_tested()).copied().collect();
+        new.sort_by_key(|x| self.ranks.rank(&x.char).unwrap_or(usize::MAX));
+        let quota = item_count.saturating_sub(items.len());
+        items.extend(new.into_iter().take(quota).cloned());
+
+        // all learnt, the weakest of the pool
+        let mut tested: Vec<&Chinese> = pool
+            .iter()
+            .filter(|x| x.is_tested() && !items.contains(x))
+            .copied()
+            .collect();
+        tested.sort_by_key(|x| x.rating);
+        let quota = item_count.saturating_sub(items.len());
+        items.extend(tested.into_iter().take(quota).cloned());
+
+        top_up(&pool, &mut items, item_count);
+        items
+    }
+}
+
+// Fill up the items with new characters of the pool, then any characters
+// of the pool, leaving out those already picked.
+fn top_up(pool: &[&Chinese], items: &mut Vec<Chinese>, item_count: usize) {
+    let mut rng = thread_rng();
+
+    let quota = item_count.saturating_sub(items.len());
+    let new = pool
+        .iter()
+        .filter(|x| !x.is_tested() && !items.contains(x))
+        .choose_multiple(&mut rng, quota);
+    items.extend(new.into_iter().map(|x| (*x).clone()));
+
+    let quota = item_count.saturating_sub(items.len());
+    let rest = pool
+        .iter()
+        .filter(|x| !items.contains(x))
+        .choose_multiple(&mut rng, quota);
+    items.extend(rest.into_iter().map(|x| (*x).clone()));
+}
+
+// What the strategies are made of: the config file and the command line
+// options, the frequency file and the history file.
+#[derive(Default)]
+pub struct StrategyConfig {
+    pub weights: SmartWeights,
+    pub ranks: Frequency,       // see cj frequency
+    pub top: Option<usize>,     // curriculum: only the top N most frequent characters
+    pub lesson: Option<Lesson>, // the lesson played, see cj lessons
+    pub history: String,        // confusions: the history file
+    pub user: Option<String>,   // confusions: whose answers
+}
+
+// All the known selection strategies.
+pub struct Strategies {
+    v: Vec<Box<dyn SelectionStrategy>>,
+}
+
+impl Strategies {
+    // Create the registry with the built-in strategies, set up from
+    // the config.
+    pub fn new(config: StrategyConfig) -> Self {
+        let mut s = Strategies { v: Vec::new() };
+        s.register(Box::new(Smart {
+            weights: config.weights.clone(),
+        }));
+        s.register(Box::new(Score));
+        s.register(Box::new(Random));
+        s.register(Box::new(WeakestFirst));
+        s.register(Box::new(DueFirst));
+        if let Some(lesson) = config.lesson {
+            s.register(Box::new(Lessons {
+                lesson,
+                ranks: config.ranks.clone(),
+            }));
+        }
+        s.register(Box::new(Curriculum {
+            weights: config.weights,
+            ranks: config.ranks,
+            top: config.top,
+        }));
+        s.register(Box::new(Confusions {
+            history: config.history,
+            user: config.user,
+        }));
+        s
+    }
+
+    // Add a strategy, replacing any strategy of the same name.
+    pub fn register(&mut self, strategy: Box<dyn SelectionStrategy>) {
+        match self.v.iter().position(|x| x.name() == strategy.name()) {
+            Some(i) => self.v[i] = strategy,
+            None => self.v.push(strategy),
+        }
+    }
+
+    // Find a strategy by name.
+    pub fn get(&self, name: &str) -> Option<&dyn SelectionStrategy> {
+        self.v.iter().find(|x| x.name() == name).map(|x| x.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn SelectionStrategy> {
+        self.v.iter().map(|x| x.as_ref())
+    }
+}
+
+#[test]
+fn test_strategies() {
+    let strategies = Strategies::new(StrategyConfig::default());
+    for name in [
+        "smart",
+        "score",
+        "random",
+        "weakest",
+        "due",
+        "curriculum",
+        "confusions",
+    ] {
+        assert_eq!(strategies.get(name).unwrap().name(), name);
+    }
+    assert!(strategies.get("nosuch").is_none());
+    assert!(strategies.get("lesson").is_none());
+
+    let strategies = Strategies::new(StrategyConfig {
+        lesson: super::lesson::lesson(5),
+        ..Default::default()
+    });
+    assert_eq!(strategies.get("lesson").unwrap().name(), "lesson");
+}
+
+#[test]
+fn test_strategy_select() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj06.csv");
+    for s in Strategies::new(StrategyConfig::default()).iter() {
+        let items = s.select(&db, 10);
+        assert_eq!(items.len(), 10, "{}", s.name());
+    }
+}
+
+#[test]
+fn test_strategy_select_empty() {
+    let db = CJDatabase::default();
+    for s in Strategies::new(StrategyConfig::default()).iter() {
+        assert!(s.select(&db, 10).is_empty(), "{}", s.name());
+    }
+}
+
+#[test]
+fn test_strategy_weakest_first() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj06.csv");
+    db.v[4].rating = -1;
+    db.v[4].due = 1;
+    db.v[5].rating = 3;
+    db.v[5].due = 1;
+
+    let items = WeakestFirst.select(&db, 10);
+    assert_eq!(items[0], db.v[4]);
+    assert_eq!(items[1], db.v[5]);
+}
+
+#[test]
+fn test_strategy_curriculum() {
+    let mut db = CJDatabase::default();
+    db.load_from("./tests/cj06.csv");
+    let chars: Vec<String> = db.v.iter().rev().map(|x| x.char.clone()).collect();
+    let curriculum = Curriculum {
+        ranks: Frequency::new(&chars),
+        ..Default::default()
+    };
+
+    // new characters, most frequent first
+    let items = curriculum.select(&db, 3);
+    let expected: Vec<Chinese> =
+        db.v.iter()
+            .rev()
+            .filter(|x| !x.is_tested())
+            .take(3)
+            .cloned()
+            .collect();
+    assert_eq!(items, expected);
+
+    // wrong answers come first, the top N only
+    db.v[0].rating = -1;
+    db.v[0].due = 1;
+    db.v[1].rating = -1;
+    db.v[1].due = 1;
+    let curriculum = Curriculum {
+        top: Some(2),
+        ..curriculum
+    };
+    let items = curriculum.select(&db, 3);
+    assert_eq!(items.len(), 2);
+    assert!(!items.contains(&db.v[0]));
+    assert!(items
+        .iter()
+        .all(|x| curriculum.ranks.in_top(&x.char, Some(2))));
+
+    // no character twice
+    let curriculum = Curriculum {
+        top: None,
+        ..curriculum
+    };
+    let items = curriculum.select(&db, db.v.len());
+    assert_eq!(items.len(), db.v.len());
+    assert!(db.v.iter().all(|x| items.contains(x)));
+}
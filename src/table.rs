@@ -0,0 +1,222 @@
+use std::fs;
+use std::path::Path;
+
+use clap::ValueEnum;
+
+use super::database::{is_valid_code, Chinese, Version};
+
+// Input method tables which can be imported, besides data files.
+// Only entries of one character and a code of 1 to 5 letters are taken,
+// phrases and symbols are skipped.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Format {
+    // Guess the format from the file name and its contents.
+    Auto,
+    // Data file, see the README.
+    Csv,
+    // Rime dictionary, e.g. cangjie5.dict.yaml: text<TAB>code<TAB>weight
+    // after the YAML header.
+    Rime,
+    // ibus-table or SCIM table: code<TAB>phrase<TAB>freq between
+    // BEGIN_TABLE and END_TABLE.
+    #[value(alias = "scim")]
+    Ibus,
+    // fcitx table source: code phrase after [Data] (fcitx 5) or [数据].
+    Fcitx,
+}
+
+// Entries read from a table.
+#[derive(Debug, Default)]
+pub struct Table {
+    pub records: Vec<Chinese>,
+    pub skipped: usize, // phrases, symbols and lines not understood
+}
+
+impl Table {
+    // Take an entry if it is one character with a cangjie code.
+    fn add(&mut self, code: &str, char: &str, version: Version) {
+        let code = code.trim().to_lowercase();
+        let char = char.trim();
+        if is_valid_code(&code) && char.chars().count() == 1 {
+            self.records.push(Chinese {
+                version,
+                ..Chinese::new(&code, char)
+            });
+        } else {
+            self.skipped += 1;
+        }
+    }
+}
+
+// Guess the format of a table from its file name and contents.
+pub fn detect(path: &str, text: &str) -> Format {
+    if path.ends_with(".yaml") || path.ends_with(".yml") {
+        return Format::Rime;
+    }
+    for line in text.lines().map(|x| x.trim()) {
+        match line {
+            "BEGIN_TABLE" | "BEGIN_DEFINITION" => return Format::Ibus,
+            "[Data]" | "[数据]" => return Format::Fcitx,
+            "..." => return Format::Rime,
+            _ => {}
+        }
+    }
+    Format::Csv
+}
+
+// Read the entries of a table, marked with a cangjie version if given.
+// The format must not be Auto or Csv, data files are loaded as usual.
+pub fn read(path: &str, format: Format, version: Version) -> Result<Table, String> {
+    if !Path::new(path).exists() {
+        return Err(format!("no such file: {}", path));
+    }
+    let text = fs::read_to_string(path).map_err(|e| format!("unable to read {}: {}", path, e))?;
+    Ok(parse(&text, format, version))
+}
+
+// Parse the entries of a table.
+pub fn parse(text: &str, format: Format, version: Version) -> Table {
+    let text = text.trim_start_matches('\u{feff}');
+    match format {
+        Format::Rime => parse_rime(text, version),
+        Format::Ibus => parse_ibus(text, version),
+        Format::Fcitx => parse_fcitx(text, version),
+        Format::Auto | Format::Csv => Table::default(),
+    }
+}
+
+// Rime: the YAML header is between "---" and "...", it may name the
+// columns, e.g. columns: [text, code, weight], the default order.
+fn parse_rime(text: &str, version: Version) -> Table {
+    let mut table = Table::default();
+    let mut columns: Vec<String> = vec!["text".to_string(), "code".to_string()];
+    let mut header = text.lines().any(|x| x.trim() == "...");
+    let mut listing = false; // reading the columns of the header
+    for line in text.lines() {
+        if header {
+            let line = line.split('#').next().unwrap_or("").trim_end();
+            if line.trim() == "..." {
+                header = false;
+            } else if let Some(rest) = line.trim().strip_prefix("columns:") {
+                let rest = rest.trim().trim_start_matches('[').trim_end_matches(']');
+                columns = rest.split(',').map(|x| x.trim().to_string()).collect();
+                columns.retain(|x| !x.is_empty());
+                listing = columns.is_empty();
+            } else if listing && line.trim().starts_with('-') {
+                columns.push(line.trim().trim_start_matches('-').trim().to_string());
+            } else {
+                listing = false;
+            }
+            continue;
+        }
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split('\t').collect();
+        let field = |name: &str| {
+            columns
+                .iter()
+                .position(|x| x == name)
+                .and_then(|i| fields.get(i))
+        };
+        match (field("code"), field("text")) {
+            (Some(code), Some(char)) => table.add(code, char, version),
+            _ => table.skipped += 1,
+        }
+    }
+    table
+}
+
+// ibus-table and SCIM: the entries are between BEGIN_TABLE and END_TABLE,
+// comments start with "###".
+fn parse_ibus(text: &str, version: Version) -> Table {
+    let mut table = Table::default();
+    let mut inside = false;
+    for line in text.lines() {
+        match line.trim() {
+            "BEGIN_TABLE" => inside = true,
+            "END_TABLE" => inside = false,
+            x if !inside || x.is_empty() || x.starts_with("###") => {}
+            x => match x.split('\t').collect::<Vec<&str>>()[..] {
+                [code, char, ..] => table.add(code, char, version),
+                _ => table.skipped += 1,
+            },
+        }
+    }
+    table
+}
+
+// fcitx: the entries follow [Data], each line a code and its phrases.
+// Prompt (&) and phrase rule (^) lines are not entries.
+fn parse_fcitx(text: &str, version: Version) -> Table {
+    let mut table = Table::default();
+    let mut inside = false;
+    for line in text.lines().map(|x| x.trim()) {
+        if line.starts_with('[') && line.ends_with(']') {
+            inside = line == "[Data]" || line == "[数据]";
+            continue;
+        }
+        if !inside || line.is_empty() || line.starts_with(['#', '&', '^']) {
+            continue;
+        }
+        let mut words = line.split_whitespace();
+        let code = words.next().unwrap_or("");
+        let mut any = false;
+        for char in words {
+            table.add(code, char, version);
+            any = true;
+        }
+        if !any {
+            table.skipped += 1;
+        }
+    }
+    table
+}
+
+#[test]
+fn test_detect() {
+    let text = fs::read_to_string("./tests/cj10.txt").unwrap();
+    assert_eq!(detect("cj10.txt", &text), Format::Ibus);
+    let text = fs::read_to_string("./tests/cj11.txt").unwrap();
+    assert_eq!(detect("cj11.txt", &text), Format::Fcitx);
+    assert_eq!(detect("cangjie5.dict.yaml", ""), Format::Rime);
+    assert_eq!(detect("cj.csv", "aombc,題,0\n"), Format::Csv);
+}
+
+#[test]
+fn test_parse_rime() {
+    let table = read("./tests/cj09.dict.yaml", Format::Rime, Version::Only(5)).unwrap();
+    let entries: Vec<(&str, &str)> = table
+        .records
+        .iter()
+        .map(|x| (x.code.as_str(), x.char.as_str()))
+        .collect();
+    assert_eq!(entries, vec![("a", "日"), ("ab", "明"), ("hqi", "我")]);
+    assert!(table.records.iter().all(|x| x.version == Version::Only(5)));
+    assert_eq!(table.skipped, 2);
+
+    // columns named in the header
+    let text = "---\nname: test\ncolumns:\n  - code\n  - text\n...\naombc\t題\n";
+    let table = parse(text, Format::Rime, Version::Any);
+    assert_eq!(table.records, vec![Chinese::new("aombc", "題")]);
+}
+
+#[test]
+fn test_parse_tables() {
+    let table = read("./tests/cj10.txt", Format::Ibus, Version::Any).unwrap();
+    assert_eq!(table.records.len(), 3);
+    assert_eq!(table.records[2], Chinese::new("aombc", "題"));
+    assert_eq!(table.skipped, 1);
+
+    let table = read("./tests/cj11.txt", Format::Fcitx, Version::Any).unwrap();
+    assert_eq!(
+        table.records,
+        vec![
+            Chinese::new("abjj", "暈"),
+            Chinese::new("abjj", "暉"),
+            Chinese::new("hqi", "我"),
+        ]
+    );
+    assert_eq!(table.skipped, 1);
+    assert!(read("./tests/none.txt", Format::Fcitx, Version::Any).is_err());
+}
@@ -0,0 +1,588 @@
+use std::io::{self, IsTerminal};
+use std::time::{Duration, Instant};
+
+use clap::ValueEnum;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Alignment, Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Gauge, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use serde::Deserialize;
+
+use super::choice;
+use super::game::{self, Mode, Question, Reply, Ui};
+use super::highscore::{self, Entry, Initials};
+use super::radical;
+
+const TICK: Duration = Duration::from_millis(250); // redraw the timer
+const SIDE_WIDTH: u16 = 44; // side panel with the keyboard
+const LOW_TIME: Duration = Duration::from_secs(10); // countdown shown in red
+
+// Cangjie keyboard, QWERTY layout.
+const KEYBOARD: [&str; 3] = ["qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+// Which front end to play with.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Frontend {
+    // Full screen if the terminal can do it, line by line otherwise.
+    Auto,
+    // Full screen terminal UI.
+    Tui,
+    // Line by line, for dumb terminals.
+    Line,
+}
+
+// Return true if the terminal can show the full screen UI.
+pub fn available() -> bool {
+    io::stdin().is_terminal()
+        && io::stdout().is_terminal()
+        && std::env::var("TERM").map_or(true, |x| x != "dumb")
+}
+
+// What the question pane shows.
+enum View {
+    Menu,                      // welcome, last score and high scores
+    Question(String, String),  // prompt, character or code
+    Initials(usize, Initials), // rank of a new high score
+}
+
+// Everything on the screen.
+struct State {
+    color: bool,
+    view: View,
+    mode: Mode,
+    number: usize,                       // current question
+    total: usize,                        // questions in the round
+    score: usize,                        // correct answers
+    start: Option<Instant>,              // start of a round being played
+    time: Duration,                      // time taken by the last round
+    deadline: Option<Instant>,           // end of the countdown, if any
+    typed: String,                       // answer being typed
+    context: String,                     // text around the character of a passage
+    hints: Vec<String>,                  // hints revealed so far
+    codes: Vec<String>,                  // valid codes in code entry mode
+    choices: Vec<String>,                // codes to pick from in choice mode
+    feedback: Option<(bool, String)>,    // last answer
+    result: Option<String>,              // score of the last round
+    board: Option<(String, Vec<Entry>)>, // high score board
+    help: &'static str,
+}
+
+// Full screen front end.
+// The terminal is restored when it is dropped.
+pub struct Tui {
+    terminal: DefaultTerminal,
+    state: State,
+}
+
+impl Tui {
+    pub fn new(color: bool) -> io::Result<Tui> {
+        Ok(Tui {
+            terminal: ratatui::try_init()?,
+            state: State::new(color && std::env::var_os("NO_COLOR").is_none()),
+        })
+    }
+
+    fn draw(&mut self) {
+        let state = &self.state;
+        // nothing to do about a broken terminal, key() will give up
+        let _ = self.terminal.draw(|frame| state.render(frame));
+    }
+
+    // Wait for a key, redrawing the screen meanwhile for the timer.
+    // Return None if the terminal is gone.
+    // Ctrl-C quits at once, as it does in line mode.
+    fn key(&mut self) -> Option<KeyEvent> {
+        loop {
+            self.draw();
+            if !event::poll(TICK).ok()? {
+                continue;
+            }
+            match event::read().ok()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    if key.modifiers.contains(KeyModifiers::CONTROL)
+                        && key.code == KeyCode::Char('c')
+                    {
+                        ratatui::restore();
+                        std::process::exit(130);
+                    }
+                    return Some(key);
+                }
+                _ => continue,
+            }
+        }
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+impl Ui for Tui {
+    fn banner(&mut self) {
+        self.state.view = View::Menu;
+        self.draw();
+    }
+
+    fn ask_continue(&mut self, mode: Mode) -> Option<Mode> {
+        self.state.view = View::Menu;
+        self.state.mode = mode;
+        self.state.help = "C continue   M switch mode   Q quit";
+        loop {
+            match self.key()?.code {
+                KeyCode::Char(ch) => match ch.to_ascii_uppercase() {
+                    'C' => return Some(self.state.mode),
+                    'M' => self.state.mode = self.state.mode.next(),
+                    'Q' => return None,
+                    _ => (),
+                },
+                KeyCode::Esc => return None,
+                _ => (),
+            }
+        }
+    }
+
+    fn begin(&mut self, total: usize) {
+        // redraw it all, in case of messages about loading ratings
+        let _ = self.terminal.clear();
+        let state = &mut self.state;
+        state.number = 0;
+        state.total = total;
+        state.score = 0;
+        state.start = Some(Instant::now());
+        state.deadline = None;
+        state.feedback = None;
+        state.result = None;
+        state.board = None;
+    }
+
+    fn ask(&mut self, question: &Question) -> Reply {
+        let state = &mut self.state;
+        let shown = match question.mode {
+            Mode::Reverse => format!(
+                "{}  {}",
+                question.chin.code,
+                radical::radicals(&question.chin.code)
+            ),
+            _ => question.chin.char.clone(),
+        };
+        if question.number > 0 {
+            state.number = question.number;
+        }
+        state.view = View::Question(question.prompt(), shown);
+        state.typed.clear();
+        state.context = question.context.to_string();
+        state.hints.clear();
+        state.codes = question.codes.to_vec();
+        state.choices = question.choices.to_vec();
+        state.help = match question.mode {
+            Mode::Code | Mode::Quick | Mode::Phrase => {
+                "Type the code, Enter or Space to answer, ? for a hint"
+            }
+            Mode::Char => "Type the character, Enter to answer, ? for a hint",
+            Mode::Reverse => "Type the character, Enter to answer",
+            Mode::Choice => "Press the number of the code, ? for a hint",
+        };
+
+        let code = question.mode.letters();
+        let mut ended = false;
+        loop {
+            // no more keys to read
+            let Some(key) = self.key() else {
+                ended = true;
+                break;
+            };
+            let typed = &mut self.state.typed;
+            match key.code {
+                KeyCode::Backspace => {
+                    typed.pop();
+                }
+                KeyCode::Char(game::HINT_KEY) => {
+                    let hints = &mut self.state.hints;
+                    if let Some(hint) = question.hints.get(hints.len()) {
+                        hints.push(hint.clone());
+                    }
+                }
+                KeyCode::Char(ch) if choice::number(ch, question.choices.len()).is_some() => {
+                    typed.push(ch);
+                    break;
+                }
+                _ if question.mode == Mode::Choice => (),
+                KeyCode::Enter | KeyCode::Char(' ') if code && !typed.is_empty() => break,
+                KeyCode::Enter if !code => break,
+                KeyCode::Char(ch)
+                    if code
+                        && ch.is_ascii_alphabetic()
+                        && typed.len() < game::max_len(&question.chin.char) =>
+                {
+                    typed.push(ch.to_ascii_lowercase())
+                }
+                KeyCode::Char(ch) if !code => typed.push(ch),
+                _ => (),
+            }
+        }
+
+        Reply {
+            text: self.state.typed.trim().to_string(),
+            hints: self.state.hints.len(),
+            ended,
+        }
+    }
+
+    fn answered(&mut self, correct: bool, score: usize, message: &str) {
+        self.state.score = score;
+        self.state.feedback = Some((correct, message.to_string()));
+    }
+
+    fn score(&mut self, score: usize, total: usize, time: Duration) {
+        let state = &mut self.state;
+        state.start = None;
+        state.deadline = None;
+        state.time = time;
+        state.result = Some(format!(
+            "Score: {} %   Time: {} seconds",
+            score * 100 / total.max(1),
+            time.as_secs()
+        ));
+        state.view = View::Menu;
+    }
+
+    fn ask_initials(&mut self, rank: usize) -> String {
+        self.state.view = View::Initials(rank, Initials::new());
+        self.state.help = "Letters or Up/Down to choose, Left/Right to move, Enter to confirm";
+        while let Some(key) = self.key() {
+            let View::Initials(_, initials) = &mut self.state.view else {
+                break;
+            };
+            match key.code {
+                KeyCode::Char(ch) => initials.letter(ch),
+                KeyCode::Up => initials.up(),
+                KeyCode::Down => initials.down(),
+                KeyCode::Left | KeyCode::Backspace => initials.left(),
+                KeyCode::Right => initials.right(),
+                KeyCode::Enter => break,
+                _ => (),
+            }
+        }
+        let value = match &self.state.view {
+            View::Initials(_, initials) => initials.value(),
+            _ => String::new(),
+        };
+        self.state.view = View::Menu;
+        value
+    }
+
+    fn board(&mut self, entries: &[Entry], title: &str) {
+        self.state.board = Some((title.to_string(), entries.to_vec()));
+    }
+
+    fn report(&mut self, text: &str) {
+        let result = self.state.result.get_or_insert_default();
+        for line in text.lines() {
+            result.push('\n');
+            result.push_str(line);
+        }
+    }
+
+    fn countdown(&mut self, deadline: Option<Instant>) {
+        self.state.deadline = deadline;
+    }
+}
+
+impl State {
+    fn new(color: bool) -> State {
+        State {
+            color,
+            view: View::Menu,
+            mode: Mode::Char,
+            number: 0,
+            total: 0,
+            score: 0,
+            start: None,
+            time: Duration::ZERO,
+            deadline: None,
+            typed: String::new(),
+            context: String::new(),
+            hints: Vec::new(),
+            codes: Vec::new(),
+            choices: Vec::new(),
+            feedback: None,
+            result: None,
+            board: None,
+            help: "",
+        }
+    }
+
+    // Foreground colour, unless colours are off.
+    fn fg(&self, color: Color) -> Style {
+        if self.color {
+            Style::new().fg(color)
+        } else {
+            Style::new()
+        }
+    }
+
+    fn elapsed(&self) -> Duration {
+        match self.start {
+            Some(start) => start.elapsed(),
+            None => self.time,
+        }
+    }
+
+    fn render(&self, frame: &mut Frame) {
+        let [title, body, progress, help] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(10),
+            Constraint::Length(3),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [main, side] =
+            Layout::horizontal([Constraint::Min(30), Constraint::Length(SIDE_WIDTH)]).areas(body);
+        let [status, keyboard, _] = Layout::vertical([
+            Constraint::Length(6),
+            Constraint::Length(5),
+            Constraint::Min(0),
+        ])
+        .areas(side);
+
+        frame.render_widget(
+            Line::from(" C J   Challenges ")
+                .style(self.fg(Color::LightGreen).add_modifier(Modifier::BOLD)),
+            title,
+        );
+        self.render_main(frame, main);
+        self.render_status(frame, status);
+        self.render_keyboard(frame, keyboard);
+
+        // no end to the questions, e.g. an arcade game
+        let (ratio, label) = if self.total == 0 {
+            (0.0, self.number.to_string())
+        } else {
+            (
+                self.number as f64 / self.total as f64,
+                format!("{}/{}", self.number, self.total),
+            )
+        };
+        frame.render_widget(
+            Gauge::default()
+                .block(Block::bordered().title(" Progress "))
+                .gauge_style(self.fg(Color::LightBlue))
+                .ratio(ratio)
+                .label(label),
+            progress,
+        );
+        frame.render_widget(Line::from(format!(" {}", self.help)), help);
+    }
+
+    fn render_main(&self, frame: &mut Frame, area: Rect) {
+        let mut lines = vec![Line::default()];
+        match &self.view {
+            View::Menu => {
+                lines.push(
+                    Line::from("Welcome to the CJ Challenges").style(self.fg(Color::LightGreen)),
+                );
+                lines.push(Line::default());
+                lines.push(Line::from(format!("Quiz mode: {}", self.mode.name())));
+                // the last answer of the round just played
+                if self.result.is_some() {
+                    self.push_feedback(&mut lines);
+                }
+                if let Some(result) = &self.result {
+                    lines.push(Line::default());
+                    for line in result.lines() {
+                        lines.push(Line::from(line).style(self.fg(Color::LightYellow)));
+                    }
+                }
+                if let Some((title, entries)) = &self.board {
+                    lines.push(Line::default());
+                    lines.push(Line::from(format!("===== {} =====", title)));
+                    lines.push(Line::from("RANK  NAME  SCORE   TIME  DATE      "));
+                    for (i, x) in entries.iter().enumerate() {
+                        lines.push(Line::from(format!(
+                            "{:>4}  {:4}  {:5}  {:>4}s  {}",
+                            i + 1,
+                            x.initials,
+                            x.score(),
+                            x.seconds,
+                            highscore::date(x.time)
+                        )));
+                    }
+                }
+            }
+            View::Question(prompt, shown) => {
+                lines.push(Line::from(prompt.trim()));
+                lines.push(Line::default());
+                if !self.context.is_empty() {
+                    lines.push(Line::from(self.context.as_str()));
+                    lines.push(Line::default());
+                }
+                lines.push(
+                    Line::from(format!("[ {} ]", shown))
+                        .style(Style::new().add_modifier(Modifier::BOLD)),
+                );
+                // the same width each, radicals are two columns wide
+                for (i, x) in self.choices.iter().enumerate() {
+                    lines.push(Line::from(format!(
+                        "{}  {:5}  {}{}",
+                        i + 1,
+                        x,
+                        radical::radicals(x),
+                        "  ".repeat(game::MAX_CODE_LEN.saturating_sub(x.len()))
+                    )));
+                }
+                for hint in self.hints.iter() {
+                    lines.push(Line::from(hint.as_str()).style(self.fg(Color::LightCyan)));
+                }
+                lines.push(Line::default());
+                lines.push(self.input());
+            }
+            View::Initials(rank, initials) => {
+                lines.push(
+                    Line::from(format!("*** NEW HIGH SCORE!  RANK {} ***", rank))
+                        .style(self.fg(Color::LightYellow)),
+                );
+                lines.push(Line::default());
+                lines.push(Line::from("Enter your initials:"));
+                lines.push(Line::from(initials.shown()));
+            }
+        }
+        if let View::Question(..) = self.view {
+            self.push_feedback(&mut lines);
+        }
+
+        frame.render_widget(
+            Paragraph::new(lines)
+                .block(Block::bordered().title(format!(" {} ", self.mode.name())))
+                .alignment(Alignment::Center),
+            area,
+        );
+    }
+
+    // Add the feedback on the last answer, if any.
+    fn push_feedback<'a>(&'a self, lines: &mut Vec<Line<'a>>) {
+        let Some((correct, message)) = &self.feedback else {
+            return;
+        };
+        let color = if *correct {
+            Color::LightGreen
+        } else {
+            Color::LightRed
+        };
+        lines.push(Line::default());
+        for line in message.lines() {
+            lines.push(Line::from(line.trim()).style(self.fg(color)));
+        }
+    }
+
+    // The answer being typed, in code entry mode green while it is on the
+    // way to one of the valid codes and red from the first wrong letter.
+    fn input(&self) -> Line<'_> {
+        let cursor = Span::styled("_", Style::new().add_modifier(Modifier::SLOW_BLINK));
+        if !self.mode.letters() || !self.typed.is_ascii() {
+            return Line::from(vec![Span::raw(self.typed.as_str()), cursor]);
+        }
+        let (ok, bad) = self
+            .typed
+            .split_at(game::prefix_len(&self.typed, &self.codes));
+        Line::from(vec![
+            Span::styled(ok, self.fg(Color::LightGreen)),
+            Span::styled(bad, self.fg(Color::LightRed)),
+            cursor,
+        ])
+    }
+
+    fn render_status(&self, frame: &mut Frame, area: Rect) {
+        let mut lines = vec![
+            Line::from(format!("Score  {}/{}", self.score, self.number)),
+            Line::from(format!("Time   {} seconds", self.elapsed().as_secs())),
+            Line::from(format!("Mode   {}", self.mode.name())),
+        ];
+        if let Some(deadline) = self.deadline {
+            let left = deadline.saturating_duration_since(Instant::now());
+            let color = if left < LOW_TIME {
+                Color::LightRed
+            } else {
+                Color::LightYellow
+            };
+            lines.push(
+                Line::from(format!("Left   {:.1} seconds", left.as_secs_f64()))
+                    .style(self.fg(color).add_modifier(Modifier::BOLD)),
+            );
+        }
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Score ")),
+            area,
+        );
+    }
+
+    // The keyboard with the radical of each key,
+    // the keys typed so far are highlighted.
+    fn render_keyboard(&self, frame: &mut Frame, area: Rect) {
+        let lines: Vec<Line> = KEYBOARD
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let mut spans = vec![Span::raw(" ".repeat(i * 2))];
+                for key in row.chars() {
+                    let label = format!(
+                        "{}{}",
+                        key.to_ascii_uppercase(),
+                        radical::radical(key).unwrap_or("?")
+                    );
+                    let style = if self.typed.contains(key) && self.mode.letters() {
+                        self.fg(Color::LightGreen).add_modifier(Modifier::REVERSED)
+                    } else {
+                        Style::new()
+                    };
+                    spans.push(Span::styled(label, style));
+                    spans.push(Span::raw(" "));
+                }
+                Line::from(spans)
+            })
+            .collect();
+        frame.render_widget(
+            Paragraph::new(lines).block(Block::bordered().title(" Cangjie keyboard ")),
+            area,
+        );
+    }
+}
+
+#[test]
+fn test_render() {
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    let mut state = State::new(false);
+    state.mode = Mode::Code;
+    state.number = 3;
+    state.total = 10;
+    state.score = 2;
+    state.view = View::Question("#3/10 ".to_string(), "題".to_string());
+    state.typed = "aox".to_string();
+    state.hints = vec!["Hint: 5 keys  _ _ _ _ _".to_string()];
+    state.codes = vec!["aombc".to_string()];
+    state.feedback = Some((false, "Wrong! 明 should be \"ab\"!  Score: 2".to_string()));
+
+    let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+    terminal.draw(|frame| state.render(frame)).unwrap();
+    let screen: String = terminal
+        .backend()
+        .buffer()
+        .content()
+        .iter()
+        .map(|x| x.symbol())
+        .collect();
+    assert!(screen.contains("#3/10"));
+    assert!(screen.contains("[ 題"));
+    assert!(screen.contains("aox_"));
+    assert!(screen.contains("Hint: 5 keys"));
+    assert!(screen.contains("should be"));
+    assert!(screen.contains("Score  2/3"));
+    assert!(screen.contains("3/10"));
+    assert!(screen.contains("A日"));
+}